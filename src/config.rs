@@ -0,0 +1,158 @@
+use crate::preprocessor::serializer::Style;
+use log::{debug, warn};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    error::Error as StdError,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+pub const FILE_NAME: &str = "smiley.toml";
+
+/// A config file that can't be loaded.
+#[derive(Debug)]
+pub enum Error {
+    Unreadable(PathBuf, io::Error),
+    Invalid(PathBuf, toml::de::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Unreadable(file, err) => {
+                write!(f, "Unable to read config file `{}`: {err}", file.display())
+            }
+            Error::Invalid(file, err) => {
+                write!(f, "Invalid config file `{}`: {err}", file.display())
+            }
+        }
+    }
+}
+
+impl StdError for Error {}
+
+/// The project configuration, which is read from `smiley.toml`.
+/// The options given on the command line take precedence over it.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub entries: Vec<PathBuf>,
+    pub out_dir: Option<PathBuf>,
+    pub load_paths: Vec<PathBuf>,
+    pub style: Option<Style>,
+    pub source_map: bool,
+    pub embed_source_map: bool,
+    pub line_comments: bool,
+    pub group_media_queries: bool,
+    pub keyframes_prefixes: Vec<String>,
+    pub precision: Option<usize>,
+    // variables that are defined before compiling the src files
+    pub defines: BTreeMap<String, String>,
+    pub lint: Option<toml::Value>,
+}
+
+impl Config {
+    /// Find the config file in the directory or its ancestors,
+    /// and load it.
+    pub fn discover(dir: &Path) -> Result<Option<Self>, Error> {
+        dir.ancestors()
+            .map(|dir| dir.join(FILE_NAME))
+            .find(|file| file.is_file())
+            .map(|file| Self::load(&file))
+            .transpose()
+    }
+
+    /// Load the config file, whose paths are resolved against its
+    /// directory.
+    pub fn load(file: &Path) -> Result<Self, Error> {
+        debug!("Loading config file `{}`", file.display());
+
+        let content =
+            fs::read_to_string(file).map_err(|err| Error::Unreadable(file.to_path_buf(), err))?;
+        let dir = file.parent().unwrap_or_else(|| Path::new(""));
+
+        Self::parse(&content, dir).map_err(|err| Error::Invalid(file.to_path_buf(), err))
+    }
+
+    /// Parse the config, whose paths are relative to the given
+    /// directory.
+    fn parse(content: &str, dir: &Path) -> Result<Self, toml::de::Error> {
+        let mut config: Self = toml::from_str(content)?;
+
+        config.entries = config.entries.iter().map(|path| dir.join(path)).collect();
+        config.out_dir = config.out_dir.map(|path| dir.join(path));
+
+        if !config.load_paths.is_empty() {
+            warn!("Imports are not supported yet, `load-paths` will be ignored");
+        }
+
+        if config.lint.is_some() {
+            warn!("Linting is not supported yet, `lint` will be ignored");
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::{
+        fixture::{FileWriteStr, PathChild, PathCreateDir},
+        TempDir,
+    };
+    use indoc::indoc;
+
+    #[test]
+    fn config_parsing() {
+        let content = indoc! {r##"
+            entries = ["main.smly", "pages/*.smly"]
+            out-dir = "dist"
+            style = "compressed"
+            source-map = true
+            keyframes-prefixes = ["webkit"]
+
+            [defines]
+            primary = "#f00"
+        "##};
+
+        let expected = Config {
+            entries: vec![
+                PathBuf::from("project/main.smly"),
+                PathBuf::from("project/pages/*.smly"),
+            ],
+            out_dir: Some(PathBuf::from("project/dist")),
+            style: Some(Style::Compressed),
+            source_map: true,
+            keyframes_prefixes: vec![String::from("webkit")],
+            defines: BTreeMap::from([(String::from("primary"), String::from("#f00"))]),
+            ..Config::default()
+        };
+
+        let config = Config::parse(content, Path::new("project")).unwrap();
+        assert_eq!(config, expected);
+
+        for content in ["style = \"pretty\"", "unknown = true", "precision = -1"] {
+            let err = Config::parse(content, Path::new(""));
+            assert!(err.is_err(), "failed at `{content}`");
+        }
+    }
+
+    #[test]
+    fn config_discovery() {
+        let dir = TempDir::new().unwrap();
+        dir.child("styles/pages").create_dir_all().unwrap();
+        dir.child(FILE_NAME).write_str("precision = 2").unwrap();
+
+        let config = Config::discover(dir.child("styles/pages").path()).unwrap();
+        assert_eq!(config.unwrap().precision, Some(2));
+
+        let dir = TempDir::new().unwrap();
+        assert_eq!(Config::discover(dir.path()).unwrap(), None);
+
+        dir.child(FILE_NAME).write_str("precision = -1").unwrap();
+        let err = Config::discover(dir.path()).unwrap_err();
+        assert!(matches!(err, Error::Invalid(..)));
+    }
+}
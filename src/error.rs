@@ -1,5 +1,6 @@
 use crate::preprocessor::line::position::Position;
-use indoc::indoc;
+use indoc::{formatdoc, indoc};
+use std::{error::Error as StdError, fmt};
 
 #[derive(Debug)]
 pub struct Error {
@@ -7,10 +8,79 @@ pub struct Error {
     pub pos: Position,
 }
 
-#[derive(Debug)]
+impl Error {
+    /// Create an error about a src as a whole (such as being
+    /// unreadable) rather than its content, which is reported at
+    /// its beginning.
+    pub fn of_file(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            pos: Position::at(1, 0),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // only the summary line of the message, followed by the
+        // details carried by the error (if any)
+        let message = self.kind.get_message();
+        let summary = message.lines().next().unwrap_or_default();
+
+        let details = match &self.kind {
+            ErrorKind::MissingDescriptor(details) => Some(*details),
+            ErrorKind::UserError(details)
+            | ErrorKind::InvalidDefine(details)
+            | ErrorKind::UnreadableFile(details)
+            | ErrorKind::UnwritableFile(details) => Some(details.as_str()),
+            _ => None,
+        };
+
+        match details {
+            Some(details) => write!(f, "{summary}: {details}")?,
+            None => write!(f, "{summary}")?,
+        }
+
+        write!(f, " at {}:{}", self.pos.row, self.pos.col)
+    }
+}
+
+impl StdError for Error {}
+
+#[derive(Debug, PartialEq)]
 pub enum ErrorKind {
     InconsistentIndentation,
     UnexpectedIndentation,
+    UnterminatedComment,
+    UnexpectedCodeAfterComment,
+    UnexpectedEof,
+    InvalidSelector,
+    UnterminatedAttributeSelector,
+    InvalidDeclaration,
+    InvalidVariableDeclaration,
+    UndefinedVariable,
+    InvalidOperand,
+    IncompatibleUnits,
+    NumberOutOfRange,
+    InvalidMediaQuery,
+    InvalidAtRulePrelude,
+    InvalidKeyframeSelector,
+    UnknownAtRule,
+    DeclarationOutsideRule,
+    UnexpectedParentSelector,
+    UnexpectedNestedRule,
+    MissingDescriptor(&'static str),
+    ExtendOutsideRule,
+    ExtendTargetNotFound,
+    ExtendAcrossMedia,
+    UserError(String),
+    InvalidDefine(String),
+    FileNotFound,
+    InvalidExtension,
+    UnreadableFile(String),
+    UnwritableFile(String),
+    MissingSrc,
+    MissingOutFile,
 }
 
 impl ErrorKind {
@@ -25,8 +95,200 @@ impl ErrorKind {
             ErrorKind::UnexpectedIndentation => indoc! {"
                 Unexpected indentation
             "},
+            ErrorKind::UnterminatedComment => indoc! {"
+                Unterminated comment
+
+                Block comments spanning multiple lines should
+                start on their own line, and be closed with `*/`
+            "},
+            ErrorKind::UnexpectedCodeAfterComment => indoc! {"
+                Unexpected code after comment
+
+                Block comments spanning multiple lines should
+                end on their own line
+            "},
+            ErrorKind::UnexpectedEof => indoc! {"
+                Unexpected end of file
+
+                The last line is continued by a trailing comma or
+                an unclosed parenthesis
+            "},
+            ErrorKind::InvalidSelector => indoc! {"
+                Invalid selector
+            "},
+            ErrorKind::UnterminatedAttributeSelector => indoc! {"
+                Unterminated attribute selector
+
+                Attribute selectors should be closed with `]`
+            "},
+            ErrorKind::InvalidDeclaration => indoc! {"
+                Invalid declaration
+
+                Declarations should be written as `property: value`
+            "},
+            ErrorKind::InvalidVariableDeclaration => indoc! {"
+                Invalid variable declaration
+
+                Variables should be declared as `$name: value`, which
+                may be followed by `!default` or `!global`
+            "},
+            ErrorKind::UndefinedVariable => indoc! {"
+                Undefined variable
+
+                Variables should be declared before they are used
+            "},
+            ErrorKind::InvalidOperand => indoc! {"
+                Invalid operand
+
+                Arithmetic operators can only be applied to numbers,
+                percentages and dimensions
+            "},
+            ErrorKind::IncompatibleUnits => indoc! {"
+                Incompatible units
+
+                The units of the operands can't be converted into
+                each other
+            "},
+            ErrorKind::NumberOutOfRange => indoc! {"
+                Number out of range
+
+                The result of the operation is too large to be
+                represented
+            "},
+            ErrorKind::InvalidMediaQuery => indoc! {"
+                Invalid media query
+            "},
+            ErrorKind::InvalidAtRulePrelude => indoc! {"
+                Invalid at-rule prelude
+            "},
+            ErrorKind::InvalidKeyframeSelector => indoc! {"
+                Invalid keyframe selector
+
+                Keyframe selectors should be either `from`, `to`,
+                or a percentage
+            "},
+            ErrorKind::UnknownAtRule => indoc! {"
+                Unknown at-rule
+            "},
+            ErrorKind::DeclarationOutsideRule => indoc! {"
+                Declaration outside of a rule
+
+                Declarations should be nested inside a selector
+            "},
+            ErrorKind::UnexpectedParentSelector => indoc! {"
+                Unexpected parent selector
+
+                The parent selector `&` can only be used inside
+                a nested rule
+            "},
+            ErrorKind::UnexpectedNestedRule => indoc! {"
+                Unexpected nested rule
+
+                Only declarations are allowed inside this block
+            "},
+            ErrorKind::ExtendOutsideRule => indoc! {"
+                Extend outside of a rule
+
+                `@extend` should be nested inside a selector
+            "},
+            ErrorKind::ExtendTargetNotFound => indoc! {"
+                Extend target not found
+
+                Add `!optional` if the target is allowed to be
+                missing
+            "},
+            ErrorKind::ExtendAcrossMedia => indoc! {"
+                Extend across media boundaries
+
+                Selectors inside a media query can only extend
+                the rules within the same media query
+            "},
+            ErrorKind::UserError(message) => {
+                return formatdoc! {"
+                    Error raised by `@error`
+
+                    {message}
+                "}
+            }
+            ErrorKind::InvalidDefine(name) => {
+                return formatdoc! {"
+                    Invalid define
+
+                    The value of the `{name}` define should be a valid
+                    expression
+                "}
+            }
+            ErrorKind::FileNotFound => indoc! {"
+                File not found
+            "},
+            ErrorKind::InvalidExtension => indoc! {"
+                Invalid extension
+
+                Smiley src files should have `.smly` extension
+            "},
+            ErrorKind::MissingSrc => indoc! {"
+                Missing src
+
+                Either a src file or a src content should be set
+                before the out file, and before running
+            "},
+            ErrorKind::MissingOutFile => indoc! {"
+                Missing out file
+
+                The out file should be set before running
+            "},
+            ErrorKind::UnreadableFile(reason) => {
+                return formatdoc! {"
+                    Unable to read file
+
+                    {reason}
+                "}
+            }
+            ErrorKind::UnwritableFile(reason) => {
+                return formatdoc! {"
+                    Unable to write file
+
+                    {reason}
+                "}
+            }
+            ErrorKind::MissingDescriptor(descriptor) => {
+                return formatdoc! {"
+                    Missing required descriptor
+
+                    This at-rule requires the `{descriptor}` descriptor
+                "}
+            }
         };
 
         String::from(msg)
     }
 }
+
+/// A problem that doesn't abort the compilation, which is also
+/// reported through the logger.
+#[derive(Debug, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub pos: Position,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            WarningKind::UserWarning(message) => {
+                write!(f, "Warning raised by `@warn`: {message}")?;
+            }
+            WarningKind::UnknownUnit(unit) => {
+                write!(f, "Unknown unit `{unit}`, passed through as is")?;
+            }
+        }
+
+        write!(f, " at {}:{}", self.pos.row, self.pos.col)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WarningKind {
+    UserWarning(String),
+    UnknownUnit(String),
+}
@@ -0,0 +1,177 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Check whether a path contains any wildcard.
+pub fn is_pattern(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?'])
+}
+
+/// Expand a glob pattern into the existing paths matching it. `*`
+/// and `?` only match within a single component, while a `**`
+/// component matches any number of directories.
+pub fn expand(pattern: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::new()];
+
+    for component in pattern.components() {
+        let part = component.as_os_str().to_string_lossy();
+
+        paths = match component {
+            Component::Normal(_) if part == "**" => {
+                paths.iter().flat_map(|path| find_dirs(path)).collect()
+            }
+
+            Component::Normal(_) if is_pattern(Path::new(&*part)) => {
+                let part = part.chars().collect::<Vec<_>>();
+
+                paths
+                    .iter()
+                    .flat_map(|path| read_dir(path))
+                    .filter(|path| {
+                        let name = path.file_name().unwrap().to_string_lossy();
+                        is_match(&part, &name.chars().collect::<Vec<_>>())
+                    })
+                    .collect()
+            }
+
+            _ => paths.iter().map(|path| path.join(component)).collect(),
+        };
+    }
+
+    paths.sort();
+    paths.dedup();
+    paths.retain(|path| path.exists());
+
+    paths
+}
+
+/// Get the leading directories of a pattern that have no wildcard,
+/// which the paths matching it are relative to.
+pub fn base(pattern: &Path) -> PathBuf {
+    pattern
+        .components()
+        .take_while(|component| !is_pattern(Path::new(component.as_os_str())))
+        .collect()
+}
+
+fn is_match(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            is_match(&pattern[1..], name) || (!name.is_empty() && is_match(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => is_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => is_match(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// Get the directory itself, along with all of its descendant
+/// directories.
+pub fn find_dirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![dir.to_path_buf()];
+
+    for path in read_dir(dir).into_iter().filter(|path| path.is_dir()) {
+        dirs.extend(find_dirs(&path));
+    }
+
+    dirs
+}
+
+/// Read the sorted paths inside a directory, where an empty path
+/// stands for the current directory. A directory that can't be read
+/// (e.g. it has been removed) is treated as empty.
+pub fn read_dir(dir: &Path) -> Vec<PathBuf> {
+    // the paths of a relative pattern are starting out empty
+    let path = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| Some(dir.join(entry.ok()?.file_name())))
+        .collect::<Vec<_>>();
+
+    paths.sort();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::{
+        fixture::{FileWriteStr, PathChild},
+        TempDir,
+    };
+
+    #[test]
+    fn wildcard_matching() {
+        let cases = [
+            ("*.smly", "main.smly", true),
+            ("*.smly", "main.css", false),
+            ("m?in.*", "main.smly", true),
+            ("*", "", true),
+            ("a*b*c", "aXbYc", true),
+            ("a*b*c", "aXbY", false),
+        ];
+
+        for (pattern, name, expected) in cases {
+            let pattern = pattern.chars().collect::<Vec<_>>();
+            let chars = name.chars().collect::<Vec<_>>();
+
+            assert_eq!(is_match(&pattern, &chars), expected, "failed at `{name}`");
+        }
+    }
+
+    #[test]
+    fn glob_expansion() {
+        let dir = TempDir::new().unwrap();
+
+        for file in ["a.smly", "b.css", "pages/c.smly", "pages/blog/d.smly"] {
+            dir.child(file).write_str("").unwrap();
+        }
+
+        let cases = [
+            ("*.smly", vec!["a.smly"]),
+            ("pages/*.smly", vec!["pages/c.smly"]),
+            (
+                "**/*.smly",
+                vec!["a.smly", "pages/blog/d.smly", "pages/c.smly"],
+            ),
+            ("*/blog", vec!["pages/blog"]),
+            ("*.scss", vec![]),
+        ];
+
+        for (pattern, expected) in cases {
+            let paths = expand(&dir.path().join(pattern));
+            let expected = expected
+                .into_iter()
+                .map(|path| dir.path().join(path))
+                .collect::<Vec<_>>();
+
+            assert_eq!(paths, expected, "failed at `{pattern}`");
+        }
+    }
+
+    #[test]
+    fn glob_base() {
+        let cases = [
+            ("styles/*.smly", "styles"),
+            ("styles/**/pages/*.smly", "styles"),
+            ("/styles/m?in.smly", "/styles"),
+            ("*.smly", ""),
+        ];
+
+        for (pattern, expected) in cases {
+            let base = base(Path::new(pattern));
+            assert_eq!(base, Path::new(expected), "failed at `{pattern}`");
+        }
+    }
+}
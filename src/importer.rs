@@ -0,0 +1,153 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+/// The storage that the src files are loaded from.
+pub trait Importer {
+    /// Resolve a path into its canonical form, relative to the src
+    /// file requesting it (if any). Returns `None` if there is no
+    /// such file.
+    fn canonicalize(&self, path: &Path, from: Option<&Path>) -> Option<PathBuf>;
+
+    /// Read the content of a canonicalized path.
+    fn read(&self, path: &Path) -> io::Result<String>;
+
+    /// Get the last modification time of a canonicalized path.
+    fn modified(&self, path: &Path) -> io::Result<SystemTime>;
+}
+
+/// Load the src files from the real filesystem.
+#[derive(Default)]
+pub struct FsImporter;
+
+impl Importer for FsImporter {
+    fn canonicalize(&self, path: &Path, from: Option<&Path>) -> Option<PathBuf> {
+        fs::canonicalize(resolve(path, from)).ok()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(path)?.modified()
+    }
+}
+
+/// Load the src files from memory, such as the ones fetched from a
+/// database or written by the tests.
+#[derive(Default)]
+pub struct MemoryImporter {
+    files: HashMap<PathBuf, (String, SystemTime)>,
+}
+
+impl MemoryImporter {
+    /// Add or replace a file, which is marked as modified now.
+    pub fn insert(&mut self, path: &Path, content: &str) {
+        self.files
+            .insert(normalize(path), (content.to_string(), SystemTime::now()));
+    }
+
+    fn get(&self, path: &Path) -> io::Result<&(String, SystemTime)> {
+        self.files.get(path).ok_or_else(|| {
+            let message = format!("`{}` does not exist", path.display());
+            io::Error::new(io::ErrorKind::NotFound, message)
+        })
+    }
+}
+
+impl Importer for MemoryImporter {
+    fn canonicalize(&self, path: &Path, from: Option<&Path>) -> Option<PathBuf> {
+        let path = normalize(&resolve(path, from));
+        self.files.contains_key(&path).then(|| path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<String> {
+        self.get(path).map(|(content, _)| content.clone())
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        self.get(path).map(|(_, modified)| *modified)
+    }
+}
+
+fn resolve(path: &Path, from: Option<&Path>) -> PathBuf {
+    match from.and_then(Path::parent) {
+        Some(dir) => dir.join(path),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Remove the `.` and `..` components of a path, without looking
+/// at the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::{
+        fixture::{FileWriteStr, PathChild},
+        TempDir,
+    };
+
+    #[test]
+    fn import_from_memory() {
+        let mut importer = MemoryImporter::default();
+        importer.insert(Path::new("styles/./main.smly"), ".a");
+        importer.insert(Path::new("styles/theme.smly"), ".b");
+
+        let main = importer
+            .canonicalize(Path::new("main.smly"), Some(Path::new("styles/theme.smly")))
+            .unwrap();
+
+        assert_eq!(main, Path::new("styles/main.smly"));
+        assert_eq!(importer.read(&main).unwrap(), ".a");
+
+        let theme = importer
+            .canonicalize(Path::new("../styles/theme.smly"), Some(&main))
+            .unwrap();
+
+        assert_eq!(theme, Path::new("styles/theme.smly"));
+
+        let modified = importer.modified(&theme).unwrap();
+        importer.insert(&theme, ".c");
+        assert!(importer.modified(&theme).unwrap() >= modified);
+
+        assert_eq!(importer.canonicalize(Path::new("other.smly"), None), None);
+        assert!(importer.read(Path::new("other.smly")).is_err());
+    }
+
+    #[test]
+    fn import_from_filesystem() {
+        let dir = TempDir::new().unwrap();
+        dir.child("styles/main.smly").write_str(".a").unwrap();
+
+        let importer = FsImporter;
+        let from = dir.child("styles/theme.smly");
+
+        let main = importer
+            .canonicalize(Path::new("./main.smly"), Some(from.path()))
+            .unwrap();
+
+        assert_eq!(importer.read(&main).unwrap(), ".a");
+        assert!(importer.modified(&main).is_ok());
+        assert_eq!(importer.canonicalize(Path::new("other.smly"), None), None);
+    }
+}
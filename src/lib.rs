@@ -2,9 +2,22 @@ extern crate pest;
 #[macro_use]
 extern crate pest_derive;
 
-pub use preprocessor::builder::Builder as PreprocessorBuilder;
+pub use config::{Config, Error as ConfigError, FILE_NAME as CONFIG_FILE_NAME};
+pub use importer::{FsImporter, Importer, MemoryImporter};
+pub use preprocessor::{
+    builder::Builder as PreprocessorBuilder,
+    compile_file, compile_str,
+    compiler::Options as CompilerOptions,
+    serializer::{Output, Style as OutputStyle},
+    Options as CompileOptions, Preprocessor,
+};
+pub use watcher::Watcher;
 
+mod config;
 pub mod error;
+pub mod glob;
+mod importer;
 mod parser;
 mod preprocessor;
 mod util;
+mod watcher;
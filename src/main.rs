@@ -4,30 +4,98 @@ use clap::StructOpt;
 use clap_verbosity_flag::{InfoLevel, Verbosity};
 use env_logger::Builder as LoggerBuilder;
 use indoc::formatdoc;
-use log::{error, info};
-use smiley::{error::Error, PreprocessorBuilder};
+use log::{error, info, warn};
+use smiley::{
+    error::{Error, ErrorKind},
+    glob, Config, OutputStyle, Preprocessor, PreprocessorBuilder, Watcher, CONFIG_FILE_NAME,
+};
 use std::{
-    fs,
+    env, fs,
+    io::{self, Read},
     path::{Path, PathBuf},
     process,
+    sync::{mpsc, Arc, Mutex},
+    thread,
     time::Duration,
 };
 
+// a src file along with its out file, which defaults to the path
+// next to the src file
+type Entry = (PathBuf, Option<PathBuf>);
+
 /// A (yet-another) simple CSS preprocessor
 #[derive(Debug, StructOpt)]
 #[clap(about, version)]
 struct Cli {
-    /// Root source file
-    src: PathBuf,
+    /// Root source files (`-` to read from stdin), glob patterns,
+    /// or directories (optionally paired with the output directory
+    /// as `src:out`). Defaults to the entries of `smiley.toml`
+    src: Vec<PathBuf>,
+
+    /// The file name of the source read from stdin, which is used
+    /// to label the messages
+    #[clap(long, value_name = "FILE")]
+    stdin_filename: Option<PathBuf>,
 
     /// Run smiley in `watch` mode
     #[clap(short, long)]
     watch: bool,
 
-    /// Specify the output file path
+    /// Specify the output file path (`-` to write to stdout), or
+    /// the output directory when compiling multiple files
     #[clap(short, long)]
     out: Option<PathBuf>,
 
+    /// Merge media queries with identical conditions into a
+    /// single block
+    #[clap(long, overrides_with = "no-group-media-queries")]
+    group_media_queries: bool,
+
+    /// Keep the media queries with identical conditions apart
+    #[clap(long, overrides_with = "group-media-queries")]
+    no_group_media_queries: bool,
+
+    /// Also emit the keyframes with the given vendor prefix
+    /// (e.g. `webkit`)
+    #[clap(long = "keyframes-prefix", value_name = "PREFIX")]
+    keyframes_prefixes: Vec<String>,
+
+    /// The number of decimal places of the computed numbers
+    /// [default: 10]
+    #[clap(long, value_name = "DIGITS")]
+    precision: Option<usize>,
+
+    /// The format of the output CSS [default: expanded]
+    #[clap(
+        long,
+        possible_values = &["expanded", "nested", "compact", "compressed"]
+    )]
+    style: Option<OutputStyle>,
+
+    /// Generate a source map next to the output file
+    #[clap(long, overrides_with = "no-source-map")]
+    source_map: bool,
+
+    /// Don't generate a source map
+    #[clap(long, overrides_with = "source-map")]
+    no_source_map: bool,
+
+    /// Embed the source map into the output file as a data URI
+    #[clap(long, overrides_with = "no-embed-source-map")]
+    embed_source_map: bool,
+
+    /// Don't embed the source map into the output file
+    #[clap(long, overrides_with = "embed-source-map")]
+    no_embed_source_map: bool,
+
+    /// Place a comment with the src file and row above each rule
+    #[clap(long, overrides_with = "no-line-comments")]
+    line_comments: bool,
+
+    /// Don't place the line comments above the rules
+    #[clap(long, overrides_with = "line-comments")]
+    no_line_comments: bool,
+
     #[structopt(flatten)]
     verbose: Verbosity<InfoLevel>,
 }
@@ -41,19 +109,475 @@ fn main() {
         .filter_level(cli.verbose.log_level_filter())
         .init();
 
-    let res = PreprocessorBuilder::default()
-        .set_src_file(&cli.src)
-        .set_out_file(cli.out.as_deref())
-        .build()
-        .run();
+    let config = match Config::discover(&env::current_dir().unwrap()) {
+        Ok(config) => config.unwrap_or_default(),
+        Err(err) => {
+            error!("{err}");
+            process::exit(1);
+        }
+    };
+
+    let is_stdin = cli.src.iter().any(|src| src == Path::new("-"));
 
-    match res {
-        Ok(duration) => log_compilation_success(duration),
+    if is_stdin && cli.src.len() > 1 {
+        error!("Unable to read from stdin along with other src files");
+        process::exit(1);
+    }
 
-        Err(err) => {
-            log_compilation_error(&cli.src, err);
+    if is_stdin && cli.watch {
+        error!("Unable to watch the source read from stdin");
+        process::exit(1);
+    }
+
+    if is_stdin {
+        let mut content = String::new();
+        let label = cli
+            .stdin_filename
+            .as_deref()
+            .unwrap_or_else(|| Path::new("stdin"));
+
+        if let Err(err) = io::stdin().read_to_string(&mut content) {
+            let err = Error::of_file(ErrorKind::UnreadableFile(err.to_string()));
+            log_compilation_error(label, "", err);
+            process::exit(1);
+        }
+
+        let builder = PreprocessorBuilder::default();
+        let result = builder
+            .set_src_content(content.clone(), cli.stdin_filename.as_deref())
+            .set_out_file(cli.out.as_deref())
+            .and_then(|builder| configure(builder, &cli, &config).build().run());
+
+        if !report(result, label, Some(&content)) {
             process::exit(1);
         }
+
+        return;
+    }
+
+    // the src files given on the command line take precedence over
+    // the entries of the config file, while the out directory of
+    // the config file is only used if there is no out path given
+    let srcs = if cli.src.is_empty() {
+        &config.entries
+    } else {
+        &cli.src
+    };
+
+    if srcs.is_empty() {
+        error!("No src files are given, either as arguments or in `{CONFIG_FILE_NAME}`");
+        process::exit(1);
+    }
+
+    let (mut entries, is_valid) = collect_entries(srcs, &cli, &config);
+
+    if !cli.watch {
+        if !compile_entries(&entries, &cli, &config) || !is_valid {
+            process::exit(1);
+        }
+
+        return;
+    }
+
+    info!("Watching {} src files for changes", entries.len());
+
+    // the src files are resolved again after every change, so that
+    // the ones added into the directories are also compiled
+    loop {
+        let files = entries
+            .iter()
+            .map(|(src, _)| src.clone())
+            .collect::<Vec<_>>();
+
+        // the watcher is created before compiling, so that the
+        // changes made during the compilation are not missed
+        let mut watcher = Watcher::new(&files, &find_watched_dirs(srcs));
+        compile_entries(&entries, &cli, &config);
+
+        watcher.wait_for_change();
+
+        info!("Change detected, recompiling");
+        entries = collect_entries(srcs, &cli, &config).0;
+    }
+}
+
+/// Resolve the src files along with their out files, where the
+/// out directory of the config file is only used if there is no out
+/// path given. All of the invalid src files are reported, returning
+/// the valid ones along with whether there was no invalid one.
+fn collect_entries(srcs: &[PathBuf], cli: &Cli, config: &Config) -> (Vec<Entry>, bool) {
+    let entries = match &cli.out {
+        Some(out) => resolve_entries(srcs, Some(out), false),
+        None => resolve_entries(srcs, config.out_dir.as_deref(), true),
+    };
+
+    let entries = match entries {
+        Ok(entries) => entries,
+        Err(message) => {
+            error!("{message}");
+            return (vec![], false);
+        }
+    };
+
+    if entries.is_empty() {
+        warn!("No src files found");
+    }
+
+    let mut is_valid = true;
+    let entries = entries
+        .into_iter()
+        .filter(|(src, _)| {
+            let is_valid_src = check_src_file(src);
+            is_valid &= is_valid_src;
+
+            is_valid_src
+        })
+        .collect();
+
+    (entries, is_valid)
+}
+
+/// Compile all of the src files, returning whether all of them
+/// have succeeded.
+fn compile_entries(entries: &[Entry], cli: &Cli, config: &Config) -> bool {
+    // the src files may have been removed since they were resolved,
+    // which is reported the same as the other errors
+    let preprocessors = entries
+        .iter()
+        .map(|(src, out)| -> Result<Preprocessor, Error> {
+            let builder = PreprocessorBuilder::default();
+            builder.set_src_file(src)?.set_out_file(out.as_deref())?;
+
+            Ok(configure(&builder, cli, config).build())
+        })
+        .collect::<Vec<_>>();
+
+    let src_files = entries
+        .iter()
+        .map(|(src, _)| src.clone())
+        .collect::<Vec<_>>();
+
+    compile_all(preprocessors, &src_files)
+}
+
+/// Apply the options that are shared by all of the src files, where
+/// the command line options take precedence over the config file.
+fn configure<'a>(
+    builder: &'a PreprocessorBuilder,
+    cli: &Cli,
+    config: &Config,
+) -> &'a PreprocessorBuilder {
+    let keyframes_prefixes = if cli.keyframes_prefixes.is_empty() {
+        &config.keyframes_prefixes
+    } else {
+        &cli.keyframes_prefixes
+    };
+
+    let defines = config
+        .defines
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect::<Vec<_>>();
+
+    let group_media_queries = flag(cli.group_media_queries, cli.no_group_media_queries);
+    let source_map = flag(cli.source_map, cli.no_source_map);
+    let embed_source_map = flag(cli.embed_source_map, cli.no_embed_source_map);
+    let line_comments = flag(cli.line_comments, cli.no_line_comments);
+
+    builder
+        .set_group_media_queries(group_media_queries.unwrap_or(config.group_media_queries))
+        .set_keyframes_prefixes(keyframes_prefixes)
+        .set_style(cli.style.or(config.style).unwrap_or_default())
+        .set_source_map(source_map.unwrap_or(config.source_map))
+        .set_embed_source_map(embed_source_map.unwrap_or(config.embed_source_map))
+        .set_line_comments(line_comments.unwrap_or(config.line_comments))
+        .set_defines(&defines);
+
+    if let Some(precision) = cli.precision.or(config.precision) {
+        builder.set_precision(precision);
+    }
+
+    builder
+}
+
+/// Combine a flag with its `--no-*` negation, which override each
+/// other so that only the last one given is set. `None` means that
+/// neither is given, leaving the value of the config file.
+fn flag(enabled: bool, disabled: bool) -> Option<bool> {
+    match (enabled, disabled) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// Resolve the src files along with their out files. The glob
+/// patterns and directories (optionally paired with their out
+/// directories as `src:out`) are expanded into their non-partial
+/// src files.
+///
+/// The out path is treated as a directory if there are multiple src
+/// files, or if any of them is expanded. The out files then mirror
+/// the paths of the src files, relative to their directories or the
+/// base directories of their patterns.
+fn resolve_entries(
+    srcs: &[PathBuf],
+    out: Option<&Path>,
+    is_out_dir: bool,
+) -> Result<Vec<Entry>, String> {
+    let is_out_dir = is_out_dir
+        || srcs.len() > 1
+        || srcs
+            .iter()
+            .any(|src| src.is_dir() || is_glob(src) || split_dir_pair(src).is_some());
+
+    if is_out_dir && out.map_or(false, |out| out == Path::new("-")) {
+        return Err(String::from("Unable to write multiple src files to stdout"));
+    }
+
+    let mut entries: Vec<Entry> = vec![];
+
+    for src in srcs {
+        for (file, out) in resolve_src(src, out, is_out_dir) {
+            let out_file = out_file_of(&file, out.as_deref());
+
+            let duplicate = entries
+                .iter()
+                .find(|(other, other_out)| out_file_of(other, other_out.as_deref()) == out_file);
+
+            match duplicate {
+                // the same src file may be matched more than once
+                Some((other, _)) if *other == file => (),
+                Some((other, _)) => {
+                    return Err(format!(
+                        "Both `{}` and `{}` are compiled into `{}`",
+                        other.display(),
+                        file.display(),
+                        out_file.display()
+                    ));
+                }
+                None => entries.push((file, out)),
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+fn resolve_src(src: &Path, out: Option<&Path>, is_out_dir: bool) -> Vec<Entry> {
+    if let Some((dir, out_dir)) = split_dir_pair(src) {
+        return mirror(find_src_files(&dir), &dir, Some(&out_dir));
+    }
+
+    if src.is_dir() {
+        return mirror(find_src_files(src), src, out);
+    }
+
+    if is_glob(src) {
+        let paths = glob::expand(src);
+
+        if paths.is_empty() {
+            warn!("No src files are matching `{}`", src.display());
+        }
+
+        let files = paths
+            .into_iter()
+            .flat_map(|path| match path {
+                path if path.is_dir() => find_src_files(&path),
+                path if is_partial(&path) => vec![],
+                path => vec![path],
+            })
+            .collect();
+
+        return mirror(files, &glob::base(src), out);
+    }
+
+    if !is_out_dir {
+        return vec![(src.to_path_buf(), out.map(Path::to_path_buf))];
+    }
+
+    let dir = src.parent().unwrap_or_else(|| Path::new(""));
+    mirror(vec![src.to_path_buf()], dir, out)
+}
+
+/// Pair the src files with their out files inside the out
+/// directory, mirroring their paths relative to the base directory.
+fn mirror(files: Vec<PathBuf>, base: &Path, out_dir: Option<&Path>) -> Vec<Entry> {
+    files
+        .into_iter()
+        .map(|file| {
+            let out = out_dir.map(|out_dir| {
+                let relative = file.strip_prefix(base).unwrap_or(&file);
+                out_dir.join(relative).with_extension("css")
+            });
+
+            (file, out)
+        })
+        .collect()
+}
+
+fn out_file_of(src: &Path, out: Option<&Path>) -> PathBuf {
+    out.map_or_else(|| src.with_extension("css"), Path::to_path_buf)
+}
+
+fn is_glob(path: &Path) -> bool {
+    !path.exists() && glob::is_pattern(path)
+}
+
+fn is_partial(file: &Path) -> bool {
+    file.file_name()
+        .map_or(false, |name| name.to_string_lossy().starts_with('_'))
+}
+
+/// Check whether the src file exists and has the `.smly` extension,
+/// reporting the problem otherwise.
+fn check_src_file(file: &Path) -> bool {
+    if !file.exists() {
+        error!("File does not exist: `{}`", file.display());
+        return false;
+    }
+
+    if file.extension().map_or(true, |ext| ext != "smly") {
+        error!("Invalid extension: Smiley src files should have `.smly` extension");
+        return false;
+    }
+
+    true
+}
+
+fn split_dir_pair(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    if path.exists() {
+        return None;
+    }
+
+    let (src, out) = path.to_str()?.split_once(':')?;
+
+    if !Path::new(src).is_dir() {
+        return None;
+    }
+
+    Some((PathBuf::from(src), PathBuf::from(out)))
+}
+
+/// Recursively find the src files inside a directory, skipping the
+/// partials (whose names start with `_`).
+fn find_src_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = vec![];
+
+    for entry in glob::read_dir(dir) {
+        if entry.is_dir() {
+            files.extend(find_src_files(&entry));
+            continue;
+        }
+
+        if entry.extension().map_or(false, |ext| ext == "smly") && !is_partial(&entry) {
+            files.push(entry);
+        }
+    }
+
+    files
+}
+
+/// Find the directories in which src files may be added, which are
+/// the src directories and the base directories of the patterns,
+/// along with all of their descendants.
+fn find_watched_dirs(srcs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut dirs = vec![];
+
+    for src in srcs {
+        let dir = match split_dir_pair(src) {
+            Some((dir, _)) => dir,
+            None if src.is_dir() => src.clone(),
+            None if is_glob(src) => glob::base(src),
+            None => continue,
+        };
+
+        // the base of a relative pattern may be empty
+        if dir.as_os_str().is_empty() {
+            dirs.extend(glob::find_dirs(Path::new(".")));
+        } else {
+            dirs.extend(glob::find_dirs(&dir));
+        }
+    }
+
+    dirs
+}
+
+/// Compile all of the src files in parallel, returning whether
+/// all of them have succeeded. All of the errors are reported in
+/// the order of the src files.
+fn compile_all(preprocessors: Vec<Result<Preprocessor, Error>>, src_files: &[PathBuf]) -> bool {
+    let (job_sender, job_receiver) = mpsc::channel();
+    let (result_sender, result_receiver) = mpsc::channel();
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+    for job in preprocessors.into_iter().enumerate() {
+        job_sender.send(job).unwrap();
+    }
+
+    drop(job_sender);
+
+    // one thread per available core, but never more than the files
+    let worker_count = thread::available_parallelism()
+        .map_or(1, |count| count.get())
+        .min(src_files.len());
+
+    let workers = (0..worker_count)
+        .map(|_| {
+            let jobs = Arc::clone(&job_receiver);
+            let results = result_sender.clone();
+
+            thread::spawn(move || loop {
+                let job = jobs.lock().unwrap().recv();
+
+                let (i, preprocessor) = match job {
+                    Ok(job) => job,
+                    Err(_) => return,
+                };
+
+                let result = preprocessor.and_then(|mut preprocessor| preprocessor.run());
+                results.send((i, result)).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    drop(result_sender);
+
+    let mut results = result_receiver.iter().collect::<Vec<_>>();
+    results.sort_by_key(|(i, ..)| *i);
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    let mut is_success = true;
+
+    for ((_, result), src) in results.into_iter().zip(src_files) {
+        is_success &= report(result, src, None);
+    }
+
+    is_success
+}
+
+/// Report the result of a compilation, returning whether it has
+/// succeeded. The src content is read again from the file when
+/// reporting an error, unless it is given (or it is unreadable).
+fn report(result: Result<Duration, Error>, label: &Path, content: Option<&str>) -> bool {
+    match result {
+        Ok(duration) => {
+            log_compilation_success(duration);
+            true
+        }
+
+        Err(err) => {
+            let content = match content {
+                Some(content) => content.to_string(),
+                None => fs::read_to_string(label).unwrap_or_default(),
+            };
+
+            log_compilation_error(label, &content, err);
+            false
+        }
     }
 }
 
@@ -62,13 +586,12 @@ fn log_compilation_success(duration: Duration) {
     info!("Successfully compiled in {duration}s");
 }
 
-fn log_compilation_error(file: &Path, error: Error) {
+fn log_compilation_error(file: &Path, content: &str, error: Error) {
     let message = error.kind.get_message();
-    let content = fs::read_to_string(file).unwrap();
     let line = content
         .lines()
         .nth(error.pos.row - 1)
-        .unwrap()
+        .unwrap_or_default()
         .escape_default();
 
     let location = format!("{}:{}:{}", file.display(), error.pos.row, error.pos.col);
@@ -5,7 +5,7 @@ pub struct SmileyParser;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use pest::{self, consumes_to};
+    use pest::{self, consumes_to, Parser};
 
     #[test]
     fn basic_selector_parsing() {
@@ -18,6 +18,18 @@ mod tests {
             "foo.bar[ baz |= 'string' i ].abc",
             "[foo=bar i][ baz ~= \"bat\" S]",
             ".foo-bar.bat[abc = ghi]",
+            ".größe",
+            "#日本",
+            ".\\31 0col",
+            ".\\@sm\\:flex",
+            ".-webkit-box",
+            "&-größe",
+            "input[disabled]",
+            "[ hidden ]",
+            "[xlink|href]",
+            "[*|href^='#']",
+            "[|lang|=en]",
+            "[lang|=en]",
         ];
 
         for case in &cases {
@@ -44,19 +56,48 @@ mod tests {
             "max-width : 100px",
             "min-width:100VW",
             "font-size: 16pt ! important",
+            "-webkit-appearance: none",
+            "display: -webkit-box",
+            "background: -moz-linear-gradient(top, #fff, #000)",
+            "color: var(--brand-color, red)",
+            "--brand-color: #f00",
+            "--empty:",
+            "--theme: { color: red; [a] (b) }",
+            "--quoted: 'a } b'",
+            "größe: 1px",
+            "--größe: 1px",
+            "grid-template-columns: 1fr 2fr",
+            "height: 100dvh",
+            "width: 50cqi",
+            "line-height: 2rlh",
+            "width: 10foo",
+            "margin: -1.5e3px +.5E+2em 1.",
+            "width: 10px - 5px",
+            "width: 10px -5px",
+            "width: 2*3px",
+            "a\\:b: 1px",
+            "--a\\:b: 1px",
         ];
 
         for case in &cases {
-            let eoi_pos = case.len();
+            let declaration = SmileyParser::parse(Rule::declaration, case)
+                .unwrap_or_else(|err| panic!("`{case}` should be parsed: {err}"))
+                .next()
+                .unwrap();
 
-            pest::parses_to! {
-                parser: SmileyParser,
-                input: case,
-                rule: Rule::declaration,
-                tokens: [
-                    declaration(0, eoi_pos),
-                ]
-            };
+            assert_eq!(declaration.as_str(), *case);
+        }
+    }
+
+    #[test]
+    fn invalid_custom_property_parsing() {
+        let cases = ["--theme: { color: red", "--theme: a )", "- -foo: 0"];
+
+        for case in &cases {
+            assert!(
+                SmileyParser::parse(Rule::declaration, case).is_err(),
+                "`{case}` should not be parsed"
+            );
         }
     }
 }
@@ -1,58 +1,412 @@
-use crate::error::Error;
-use itertools::Itertools;
+use crate::{
+    error::{Error, ErrorKind},
+    importer::{FsImporter, Importer},
+    util,
+};
+use compiler::{Compiler, Options as CompilerOptions};
 use line::{builder::Builder as LineBuilder, Line, NumberedLine};
-use log::{debug, info};
+use log::{debug, info, warn};
+use serializer::{Output, Serializer, Style};
+use source_map::SourceMap;
 use std::{
+    ffi::OsString,
     fs,
-    path::PathBuf,
+    io::{self, Write},
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
 pub mod builder;
+pub mod compiler;
+pub mod css;
+pub mod expression;
+pub mod extend;
 pub mod line;
+pub mod serializer;
+pub mod source_map;
+pub mod tree;
+pub mod unit;
+pub mod variables;
+
+/// The options of compiling a src into CSS, which wrap the options
+/// of the compiler with the ones of the output.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    pub style: Style,
+    pub group_media_queries: bool,
+    // place a comment with the src file and row above each rule
+    pub line_comments: bool,
+    pub compiler: CompilerOptions,
+}
+
+/// Compile a src into CSS without touching the filesystem, where
+/// all of the failures are returned as errors.
+pub fn compile_str(src: &str, options: &Options) -> Result<Output, Error> {
+    let lines = build_lines(src)?;
+    let tree = tree::build(&lines);
+
+    let compiler = Compiler::new(options.compiler.clone());
+    let (stylesheet, warnings) = compiler.compile(&tree)?;
+
+    let serializer = Serializer {
+        group_media_queries: options.group_media_queries,
+        style: options.style,
+        line_comments: options.line_comments.then(|| src_label(options)),
+    };
+
+    let mut output = serializer.serialize(stylesheet);
+    output.warnings = warnings;
+
+    Ok(output)
+}
+
+/// Compile a src file that is loaded through the importer.
+pub fn compile_file(
+    file: &Path,
+    options: &Options,
+    importer: &dyn Importer,
+) -> Result<Output, Error> {
+    let (file, src) = load_file(file, importer)?;
+
+    let mut options = options.clone();
+    options.compiler.file = Some(file);
+
+    compile_str(&src, &options)
+}
 
 #[derive(Default)]
 pub struct Preprocessor {
     src: Option<PathBuf>,
+    // the src read from stdin, in which case the src file is
+    // optional and only used to label the messages
+    src_content: Option<String>,
+    // `-` for stdout
     out: Option<PathBuf>,
+    options: Options,
+    source_map: bool,
+    embed_source_map: bool,
 }
 
 impl Preprocessor {
     pub fn run(&mut self) -> Result<Duration, Error> {
-        assert!(self.src.is_some(), "src file is not setted properly");
-        assert!(self.out.is_some(), "out file is not setted properly");
+        if self.src.is_none() && self.src_content.is_none() {
+            return Err(Error::of_file(ErrorKind::MissingSrc));
+        }
+
+        if self.out.is_none() {
+            return Err(Error::of_file(ErrorKind::MissingOutFile));
+        }
 
         info!("Running the preprocessor");
 
         let start = Instant::now();
-        let lines = self.read_src_file_lines()?;
+        let content = self.read_src_file()?;
+        let output = compile_str(&content, &self.options)?;
 
-        for (line, next) in lines.iter().tuple_windows() {
-            let (_, line) = line;
-            let (_, next) = next;
-            let _kind = line::determine_kind(line, next);
+        if self.source_map || self.embed_source_map {
+            self.write_out_file_with_source_map(output, &content)?;
+        } else {
+            self.write_out_file(&output.css)?;
         }
 
         Ok(start.elapsed())
     }
 
-    fn read_src_file_lines(&self) -> Result<Vec<NumberedLine>, Error> {
+    fn read_src_file(&self) -> Result<String, Error> {
+        if let Some(content) = &self.src_content {
+            return Ok(content.clone());
+        }
+
         debug!("Reading src file content");
 
+        // the src file is still labeled by its original path
         let file_path = self.src.as_ref().unwrap();
-        let file_content = fs::read_to_string(file_path).unwrap();
+        let (_, content) = load_file(file_path, &FsImporter)?;
+
+        Ok(content)
+    }
+
+    fn write_out_file(&self, content: &str) -> Result<(), Error> {
+        let file_path = self.out.as_ref().unwrap();
+
+        if util::is_std_stream(file_path) {
+            debug!("Writing out content to stdout");
+            return io::stdout()
+                .write_all(content.as_bytes())
+                .map_err(|err| write_error(file_path, err));
+        }
+
+        debug!("Writing out file content");
 
-        let mut line_builder = LineBuilder::default();
-        let mut lines = vec![];
+        if let Some(dir) = file_path.parent() {
+            fs::create_dir_all(dir).map_err(|err| write_error(dir, err))?;
+        }
 
-        for raw_line in file_content.lines() {
-            if let Some(line) = line_builder.build_line_from(raw_line)? {
-                lines.push(line);
+        fs::write(file_path, content).map_err(|err| write_error(file_path, err))
+    }
+
+    /// Write the out file, along with its source map that is
+    /// either embedded into the out file or placed next to it
+    /// (with `.map` appended to its name). The source map is always
+    /// embedded when writing to stdout.
+    fn write_out_file_with_source_map(
+        &self,
+        output: Output,
+        src_content: &str,
+    ) -> Result<(), Error> {
+        let out = self.out.as_ref().unwrap();
+        let is_stdout = util::is_std_stream(out);
+
+        if is_stdout && !self.embed_source_map {
+            warn!("Source map is embedded, as the output is written to stdout");
+        }
+
+        let mut map_file = OsString::from(out);
+        map_file.push(".map");
+        let map_file = PathBuf::from(map_file);
+
+        let out_dir = out.parent().unwrap_or_else(|| Path::new(""));
+
+        let source = match &self.options.compiler.file {
+            Some(src) => {
+                util::relative_url(src, out_dir).map_err(|err| write_error(&map_file, err))?
             }
+            None => src_label(&self.options),
+        };
+
+        let source_map = SourceMap::new(
+            &if is_stdout {
+                String::new()
+            } else {
+                file_name(out)
+            },
+            &source,
+            src_content,
+            &output.css,
+            &output.mappings,
+        );
+
+        let url = if self.embed_source_map || is_stdout {
+            source_map.to_data_uri()
+        } else {
+            debug!("Writing source map to `{}`", map_file.display());
+            fs::write(&map_file, source_map.to_json())
+                .map_err(|err| write_error(&map_file, err))?;
+
+            file_name(&map_file)
+        };
+
+        let mut css = output.css;
+
+        if !css.is_empty() && !css.ends_with('\n') {
+            css.push('\n');
         }
 
-        lines.push((0, Line::eof()));
+        css.push_str(&format!("\n/*# sourceMappingURL={url} */\n"));
+
+        self.write_out_file(&css)
+    }
+}
+
+/// Load a src file through the importer, returning its canonical
+/// path along with its content.
+fn load_file(file: &Path, importer: &dyn Importer) -> Result<(PathBuf, String), Error> {
+    let file = importer
+        .canonicalize(file, None)
+        .ok_or_else(|| Error::of_file(ErrorKind::FileNotFound))?;
+
+    let content = importer
+        .read(&file)
+        .map_err(|err| Error::of_file(ErrorKind::UnreadableFile(err.to_string())))?;
+
+    Ok((file, content))
+}
+
+fn write_error(file: &Path, err: io::Error) -> Error {
+    let reason = format!("`{}`: {err}", file.display());
+    Error::of_file(ErrorKind::UnwritableFile(reason))
+}
+
+fn src_label(options: &Options) -> String {
+    match &options.compiler.file {
+        Some(file) => file.display().to_string(),
+        None => String::from("stdin"),
+    }
+}
+
+fn build_lines(content: &str) -> Result<Vec<NumberedLine>, Error> {
+    let mut line_builder = LineBuilder::default();
+    let mut lines = vec![];
+
+    for raw_line in content.lines() {
+        lines.extend(line_builder.build_line_from(raw_line)?);
+    }
+
+    lines.extend(line_builder.finish()?);
+
+    lines.push((0, Line::eof()));
+
+    Ok(lines)
+}
+
+fn file_name(file: &Path) -> String {
+    file.file_name().unwrap().to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{builder::Builder, *};
+    use crate::importer::MemoryImporter;
+    use indoc::indoc;
+
+    #[test]
+    fn compile_from_str() {
+        let src = indoc! {"
+            $gap: 10px
+            .a
+                margin: $gap
+        "};
+
+        let options = Options {
+            style: Style::Compressed,
+            line_comments: true,
+            ..Options::default()
+        };
+
+        let output = compile_str(src, &options).unwrap();
+        assert_eq!(output.css, ".a{margin:10px}\n");
+
+        let err = compile_str(".a\n    color red\n", &options).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidDeclaration);
+        assert_eq!((err.pos.row, err.pos.col), (2, 4));
+        assert_eq!(err.to_string(), "Invalid declaration at 2:4");
+
+        let err = compile_str(".a\n    @error \"bad\"\n", &options).unwrap_err();
+        assert_eq!(err.to_string(), "Error raised by `@error`: bad at 2:4");
+    }
+
+    #[test]
+    fn compile_values_spanning_lines() {
+        let src = indoc! {r#"
+            .grid
+                grid-template-areas: "a b"
+                    "c d"
+                box-shadow: 0 0 1px red,
+                    0 0 2px blue
+        "#};
+
+        let expected = indoc! {r#"
+            .grid {
+              grid-template-areas: "a b" "c d";
+              box-shadow: 0 0 1px red, 0 0 2px blue;
+            }
+        "#};
+
+        let output = compile_str(src, &Options::default()).unwrap();
+        assert_eq!(output.css, expected);
+    }
+
+    #[test]
+    fn compile_inline_comments() {
+        let src = indoc! {"
+            /* a */ .a
+                color: red /* b */
+                margin: 0 /*! keep */
+        "};
+
+        let expected = indoc! {"
+            /* a */
+
+            /* b */
+
+            /*! keep */
+
+            .a {
+              color: red;
+              margin: 0;
+            }
+        "};
+
+        let output = compile_str(src, &Options::default()).unwrap();
+        assert_eq!(output.css, expected);
+
+        let options = Options {
+            style: Style::Compressed,
+            ..Options::default()
+        };
+
+        let output = compile_str(src, &options).unwrap();
+        assert_eq!(output.css, "/*! keep */.a{color:red;margin:0}\n");
+    }
+
+    #[test]
+    fn compile_with_warnings() {
+        let src = indoc! {"
+            .a
+                @warn \"careful\"
+                width: 10foo
+        "};
+
+        let output = compile_str(src, &Options::default()).unwrap();
+
+        let warnings = output
+            .warnings
+            .iter()
+            .map(|warning| warning.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            warnings,
+            vec![
+                "Warning raised by `@warn`: careful at 2:4",
+                "Unknown unit `foo`, passed through as is at 3:13",
+            ]
+        );
+    }
+
+    #[test]
+    fn build_with_invalid_src() {
+        let builder = Builder::default();
+
+        let err = builder.set_src_file(Path::new("missing.smly")).err();
+        assert_eq!(err.unwrap().kind, ErrorKind::FileNotFound);
+
+        let err = builder.set_src_file(Path::new("Cargo.toml")).err();
+        assert_eq!(err.unwrap().kind, ErrorKind::InvalidExtension);
+
+        let err = builder.set_out_file(None).err();
+        assert_eq!(err.unwrap().kind, ErrorKind::MissingSrc);
+
+        let err = builder.build().run().unwrap_err();
+        assert_eq!(err.kind, ErrorKind::MissingSrc);
+
+        builder.set_src_content(String::new(), None);
+        let err = builder.build().run().unwrap_err();
+        assert_eq!(err.kind, ErrorKind::MissingOutFile);
+    }
+
+    #[test]
+    fn compile_from_importer() {
+        let mut importer = MemoryImporter::default();
+        importer.insert(Path::new("styles/main.smly"), ".a\n    color: red\n");
+
+        let options = Options {
+            line_comments: true,
+            ..Options::default()
+        };
+
+        let expected = indoc! {"
+            /* styles/main.smly:1 */
+            .a {
+              color: red;
+            }
+        "};
+
+        let file = Path::new("styles/./main.smly");
+        let output = compile_file(file, &options, &importer).unwrap();
+        assert_eq!(output.css, expected);
 
-        Ok(lines)
+        let err = compile_file(Path::new("other.smly"), &options, &importer).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::FileNotFound);
     }
 }
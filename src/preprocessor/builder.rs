@@ -1,6 +1,13 @@
-use crate::{preprocessor::Preprocessor, util};
+use crate::{
+    error::{Error, ErrorKind},
+    preprocessor::{serializer::Style, Preprocessor},
+    util,
+};
 use log::debug;
-use std::{cell::RefCell, path::Path};
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+};
 
 #[derive(Default)]
 pub struct Builder {
@@ -8,34 +15,121 @@ pub struct Builder {
 }
 
 impl Builder {
-    pub fn set_src_file(&self, file: &Path) -> &Self {
-        util::assert_file_exists(file);
-        util::assert_src_file_extension(file);
+    pub fn set_src_file(&self, file: &Path) -> Result<&Self, Error> {
+        if !file.exists() {
+            return Err(Error::of_file(ErrorKind::FileNotFound));
+        }
+
+        if file.extension().map_or(true, |ext| ext != "smly") {
+            return Err(Error::of_file(ErrorKind::InvalidExtension));
+        }
 
         debug!("Setting src file to `{}`", file.display());
-        self.preprocessor.borrow_mut().src = Some(file.to_path_buf());
+        let mut preprocessor = self.preprocessor.borrow_mut();
+        preprocessor.src = Some(file.to_path_buf());
+        preprocessor.options.compiler.file = Some(file.to_path_buf());
+
+        Ok(self)
+    }
+
+    /// Use the given content (e.g. read from stdin) as the src,
+    /// which may be labeled by a file name in the messages.
+    pub fn set_src_content(&self, content: String, file: Option<&Path>) -> &Self {
+        debug!("Setting src content from stdin");
+
+        let mut preprocessor = self.preprocessor.borrow_mut();
+        preprocessor.src = None;
+        preprocessor.src_content = Some(content);
+        preprocessor.options.compiler.file = file.map(Path::to_path_buf);
 
         self
     }
 
-    pub fn set_out_file(&self, file: Option<&Path>) -> &Self {
+    /// Set the out file, which defaults to stdout for the src
+    /// content, or to the path next to the src file. The src should
+    /// be set beforehand.
+    pub fn set_out_file(&self, file: Option<&Path>) -> Result<&Self, Error> {
         let file = match file {
             Some(f) => f.to_path_buf(),
+            None if self.preprocessor.borrow().src_content.is_some() => {
+                debug!("No out file specified. Use stdout");
+                PathBuf::from("-")
+            }
             None => {
                 debug!("No out file specified. Use default value");
-                util::create_default_out_file_pathbuf(
-                    self.preprocessor
-                        .borrow_mut()
-                        .src
-                        .as_deref()
-                        .expect("src file is not setted properly"),
-                )
+                let preprocessor = self.preprocessor.borrow();
+                let src = preprocessor
+                    .src
+                    .as_deref()
+                    .ok_or_else(|| Error::of_file(ErrorKind::MissingSrc))?;
+
+                util::create_default_out_file_pathbuf(src)
             }
         };
 
         debug!("Setting out file to `{}`", file.display());
         self.preprocessor.borrow_mut().out = Some(file);
 
+        Ok(self)
+    }
+
+    pub fn set_group_media_queries(&self, value: bool) -> &Self {
+        debug!("Setting media queries grouping to `{value}`");
+        self.preprocessor.borrow_mut().options.group_media_queries = value;
+
+        self
+    }
+
+    pub fn set_keyframes_prefixes(&self, prefixes: &[String]) -> &Self {
+        debug!("Setting keyframes prefixes to `{prefixes:?}`");
+        self.preprocessor
+            .borrow_mut()
+            .options
+            .compiler
+            .keyframes_prefixes = prefixes.to_vec();
+
+        self
+    }
+
+    pub fn set_precision(&self, precision: usize) -> &Self {
+        debug!("Setting numeric precision to `{precision}`");
+        self.preprocessor.borrow_mut().options.compiler.precision = precision;
+
+        self
+    }
+
+    pub fn set_style(&self, style: Style) -> &Self {
+        debug!("Setting output style to `{style:?}`");
+        self.preprocessor.borrow_mut().options.style = style;
+
+        self
+    }
+
+    pub fn set_source_map(&self, value: bool) -> &Self {
+        debug!("Setting source map generation to `{value}`");
+        self.preprocessor.borrow_mut().source_map = value;
+
+        self
+    }
+
+    pub fn set_embed_source_map(&self, value: bool) -> &Self {
+        debug!("Setting source map embedding to `{value}`");
+        self.preprocessor.borrow_mut().embed_source_map = value;
+
+        self
+    }
+
+    pub fn set_line_comments(&self, value: bool) -> &Self {
+        debug!("Setting line comments to `{value}`");
+        self.preprocessor.borrow_mut().options.line_comments = value;
+
+        self
+    }
+
+    pub fn set_defines(&self, defines: &[(String, String)]) -> &Self {
+        debug!("Setting defined variables to `{defines:?}`");
+        self.preprocessor.borrow_mut().options.compiler.defines = defines.to_vec();
+
         self
     }
 
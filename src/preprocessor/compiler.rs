@@ -0,0 +1,1711 @@
+use super::{
+    css::{AtRule, Declaration, Item, Rule, Stylesheet},
+    expression::{self, DEFAULT_PRECISION},
+    extend::{self, Extension},
+    line::{indentation::Indentation, position::Position, Content as LineContent, Line, LineKind},
+    tree::Node,
+    unit,
+    variables::Variables,
+};
+use crate::{
+    error::{Error, ErrorKind, Warning, WarningKind},
+    parser::{Rule as ParserRule, SmileyParser},
+};
+use itertools::Itertools;
+use log::{debug, warn};
+use pest::{
+    error::{Error as PestError, LineColLocation},
+    iterators::Pair,
+    Parser,
+};
+use std::{mem, path::PathBuf};
+
+const PAGE_MARGIN_BOXES: &[&str] = &[
+    "top-left-corner",
+    "top-left",
+    "top-center",
+    "top-right",
+    "top-right-corner",
+    "bottom-left-corner",
+    "bottom-left",
+    "bottom-center",
+    "bottom-right",
+    "bottom-right-corner",
+    "left-top",
+    "left-middle",
+    "left-bottom",
+    "right-top",
+    "right-middle",
+    "right-bottom",
+];
+
+const REQUIRED_DESCRIPTORS: &[(&str, &[&str])] = &[
+    ("font-face", &["font-family", "src"]),
+    ("property", &["syntax", "inherits"]),
+];
+
+#[derive(Clone, Default)]
+struct Context {
+    selectors: Vec<String>,
+    groups: Vec<Group>,
+    // the position of the innermost selector
+    pos: Option<Position>,
+}
+
+/// A conditional group at-rule (such as `@media`) that the
+/// nested rules will be bubbled up into.
+#[derive(Clone)]
+struct Group {
+    name: String,
+    // the comma-separated parts of the at-rule prelude
+    prelude: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    // vendor prefixes (e.g. `webkit`) in which the keyframes
+    // should also be duplicated into
+    pub keyframes_prefixes: Vec<String>,
+    // the number of decimal places of the computed numbers
+    pub precision: usize,
+    // the src file, which is used to locate the messages of
+    // `@debug` and `@warn`
+    pub file: Option<PathBuf>,
+    // variables that are defined before compiling, which take
+    // precedence over the `!default` ones
+    pub defines: Vec<(String, String)>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            keyframes_prefixes: vec![],
+            precision: DEFAULT_PRECISION,
+            file: None,
+            defines: vec![],
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Compiler {
+    options: Options,
+    stylesheet: Stylesheet,
+    extensions: Vec<Extension>,
+    variables: Variables,
+    warnings: Vec<Warning>,
+}
+
+impl Compiler {
+    pub fn new(options: Options) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Compile the trees into a stylesheet, along with the warnings
+    /// reported while compiling.
+    pub fn compile(mut self, nodes: &[Node]) -> Result<(Stylesheet, Vec<Warning>), Error> {
+        self.variables.push_scope();
+
+        for (name, value) in self.options.defines.clone() {
+            let value = self.evaluate_define(&name, &value)?;
+            self.variables.set(&name, value, false);
+        }
+
+        // the top-level variables are declared into the root scope,
+        // where the `!global` ones are assigned into
+        self.compile_block_items(nodes, &Context::default())?;
+
+        let items = extend::apply(self.stylesheet.items, &self.extensions)?;
+
+        Ok((Stylesheet { items }, self.warnings))
+    }
+
+    /// Evaluate the value of a define, which should be a valid
+    /// expression the same as the value of a variable. The errors
+    /// are reported at the beginning of the src.
+    fn evaluate_define(&self, name: &str, value: &str) -> Result<String, Error> {
+        let error = Error {
+            kind: ErrorKind::InvalidDefine(name.to_string()),
+            pos: Position::at(1, 0),
+        };
+
+        let is_valid =
+            SmileyParser::parse(ParserRule::declaration_value, value).map_or(false, |pairs| {
+                pairs
+                    .flatten()
+                    .all(|pair| pair.as_rule() != ParserRule::important)
+            });
+
+        if !is_valid {
+            return Err(error);
+        }
+
+        let node = Node {
+            row: 1,
+            line: Line {
+                content: LineContent::Value(value.to_string()),
+                indentation: Indentation::none(),
+                continuations: vec![],
+            },
+            kind: LineKind::Declaration,
+            children: vec![],
+        };
+
+        expression::evaluate(&node, value, 0, self.options.precision, &self.variables)
+            .map_err(|_| error)
+    }
+
+    fn compile_block(&mut self, nodes: &[Node], ctx: &Context) -> Result<(), Error> {
+        self.variables.push_scope();
+        let result = self.compile_block_items(nodes, ctx);
+        self.variables.pop_scope();
+
+        result
+    }
+
+    fn compile_block_items(&mut self, nodes: &[Node], ctx: &Context) -> Result<(), Error> {
+        let mut declarations = vec![];
+
+        for node in nodes {
+            // comments inside a rule are placed right before the
+            // rule, as rules can only contain declarations
+            if node.is_comment() {
+                self.emit(Item::Comment(node.content().to_string()), ctx);
+                continue;
+            }
+
+            if node.content().starts_with('@') {
+                self.flush_declarations(&mut declarations, ctx);
+                self.compile_at_rule(node, ctx)?;
+                continue;
+            }
+
+            if node.content().starts_with('$') {
+                self.compile_variable(node)?;
+                continue;
+            }
+
+            match node.kind {
+                LineKind::Declaration | LineKind::PropertyNamespace => {
+                    if ctx.selectors.is_empty() {
+                        return Err(error_at(node, ErrorKind::DeclarationOutsideRule, 0));
+                    }
+
+                    declarations.extend(self.parse_declarations(node)?);
+                }
+
+                LineKind::Selector => {
+                    self.flush_declarations(&mut declarations, ctx);
+                    self.compile_rule(node, ctx)?;
+                }
+            }
+        }
+
+        self.flush_declarations(&mut declarations, ctx);
+
+        Ok(())
+    }
+
+    fn compile_variable(&mut self, node: &Node) -> Result<(), Error> {
+        if let Some(child) = node.children.first() {
+            return Err(error_at(child, ErrorKind::UnexpectedNestedRule, 0));
+        }
+
+        let content = node.content();
+        let declaration = SmileyParser::parse(ParserRule::variable_declaration, content)
+            .map_err(|err| syntax_error(node, ErrorKind::InvalidVariableDeclaration, 0, err))?
+            .next()
+            .unwrap();
+
+        let mut name = "";
+        let mut value = String::new();
+        let mut is_default = false;
+        let mut is_global = false;
+
+        for pair in declaration.into_inner() {
+            match pair.as_rule() {
+                ParserRule::variable => name = &pair.as_str()[1..],
+                ParserRule::expr => {
+                    let offset = pair.as_span().start_pos().line_col().1 - 1;
+                    self.warn_unknown_units(node, pair.as_str(), offset);
+
+                    value = expression::evaluate(
+                        node,
+                        pair.as_str(),
+                        offset,
+                        self.options.precision,
+                        &self.variables,
+                    )?;
+                }
+                ParserRule::variable_flag => {
+                    let flag = pair.as_str()[1..].trim_start();
+                    is_default |= flag.eq_ignore_ascii_case("default");
+                    is_global |= flag.eq_ignore_ascii_case("global");
+                }
+                _ => (),
+            }
+        }
+
+        // `!default` only assigns the variables that are not set
+        if is_default && self.variables.get(name).is_some() {
+            return Ok(());
+        }
+
+        self.variables.set(name, value, is_global);
+
+        Ok(())
+    }
+
+    /// Report the evaluated expression of `@debug` or `@warn`
+    /// through the logger (collecting the warnings as well), or
+    /// abort the compilation on `@error`.
+    fn compile_directive(
+        &mut self,
+        node: &Node,
+        name: &str,
+        expr: Pair<ParserRule>,
+        prelude_offset: usize,
+    ) -> Result<(), Error> {
+        if let Some(child) = node.children.first() {
+            return Err(error_at(child, ErrorKind::UnexpectedNestedRule, 0));
+        }
+
+        let offset = prelude_offset + expr.as_span().start_pos().line_col().1 - 1;
+        let message = expression::evaluate(
+            node,
+            expr.as_str(),
+            offset,
+            self.options.precision,
+            &self.variables,
+        )?;
+        let message = unquote(&message);
+
+        let pos = node.position_at(0);
+        let location = match &self.options.file {
+            Some(file) => format!("{}:{}:{}", file.display(), pos.row, pos.col),
+            None => format!("{}:{}", pos.row, pos.col),
+        };
+
+        match name {
+            "debug" => debug!("{location}: {message}"),
+            "warn" => {
+                warn!("{location}: {message}");
+
+                self.warnings.push(Warning {
+                    kind: WarningKind::UserWarning(message.to_string()),
+                    pos,
+                });
+            }
+            _ => return Err(error_at(node, ErrorKind::UserError(message.to_string()), 0)),
+        }
+
+        Ok(())
+    }
+
+    fn compile_rule(&mut self, node: &Node, ctx: &Context) -> Result<(), Error> {
+        let ctx = Context {
+            selectors: parse_selector(node, &ctx.selectors)?,
+            pos: Some(node.position_at(0)),
+            ..ctx.clone()
+        };
+
+        self.compile_block(&node.children, &ctx)
+    }
+
+    fn compile_at_rule(&mut self, node: &Node, ctx: &Context) -> Result<(), Error> {
+        let content = node.content();
+        let name_len = content
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(content.len());
+
+        let name = &content[1..name_len];
+        let prelude = content[name_len..].trim_start();
+        // error columns are counted in chars
+        let prelude_offset = content[..content.len() - prelude.len()].chars().count();
+
+        let rule = match name {
+            "media" => ParserRule::media_query_list,
+            "supports" => ParserRule::supports_condition,
+            "container" => ParserRule::container_query,
+            "layer" => ParserRule::layer_prelude,
+            "scope" => ParserRule::scope_prelude,
+            "keyframes" | "-webkit-keyframes" | "-moz-keyframes" | "-o-keyframes" => {
+                ParserRule::keyframes_name
+            }
+            "font-face" => ParserRule::font_face_prelude,
+            "page" => ParserRule::page_prelude,
+            "property" => ParserRule::property_prelude,
+            "counter-style" => ParserRule::counter_style_prelude,
+            "extend" => ParserRule::extend_prelude,
+            "debug" | "warn" | "error" => ParserRule::directive_prelude,
+            _ => return Err(error_at(node, ErrorKind::UnknownAtRule, 0)),
+        };
+
+        let error_kind = match name {
+            "media" => ErrorKind::InvalidMediaQuery,
+            _ => ErrorKind::InvalidAtRulePrelude,
+        };
+
+        let parsed_prelude = SmileyParser::parse(rule, prelude)
+            .map_err(|err| syntax_error(node, error_kind, prelude_offset, err))?
+            .next()
+            .unwrap();
+
+        match name {
+            "media" => {
+                let queries = parsed_prelude
+                    .into_inner()
+                    .filter(|pair| pair.as_rule() == ParserRule::media_query)
+                    .map(|query| normalize_whitespaces(query.as_str()))
+                    .collect();
+
+                self.compile_media(node, queries, ctx)
+            }
+
+            "layer" => {
+                let names = parsed_prelude
+                    .into_inner()
+                    .filter(|pair| pair.as_rule() == ParserRule::layer_name)
+                    .map(|name| name.as_str().to_string())
+                    .collect::<Vec<_>>();
+
+                self.compile_layer(node, names, prelude_offset, ctx)
+            }
+
+            "extend" => {
+                let mut target = String::new();
+                let mut is_optional = false;
+
+                for pair in parsed_prelude.into_inner() {
+                    match pair.as_rule() {
+                        ParserRule::extend_target => target = pair.as_str().to_string(),
+                        ParserRule::optional_flag => is_optional = true,
+                        _ => (),
+                    }
+                }
+
+                self.compile_extend(node, target, is_optional, ctx)
+            }
+
+            "debug" | "warn" | "error" => {
+                let expr = parsed_prelude
+                    .into_inner()
+                    .find(|pair| pair.as_rule() == ParserRule::expr)
+                    .unwrap();
+
+                self.compile_directive(node, name, expr, prelude_offset)
+            }
+
+            name if name.ends_with("keyframes") => {
+                self.compile_keyframes(node, name, prelude.trim_end(), ctx)
+            }
+
+            "font-face" | "page" | "property" | "counter-style" => {
+                self.compile_descriptor_block(node, name, &normalize_whitespaces(prelude), ctx)
+            }
+
+            _ => {
+                let group = Group {
+                    name: name.to_string(),
+                    prelude: vec![normalize_whitespaces(prelude)],
+                };
+
+                self.compile_group(node, group, ctx)
+            }
+        }
+    }
+
+    fn compile_media(
+        &mut self,
+        node: &Node,
+        queries: Vec<String>,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        let mut ctx = ctx.clone();
+
+        match ctx.groups.last_mut() {
+            // media queries directly nested inside another media
+            // query will only be applied when both conditions
+            // are met, so they can be merged into one
+            Some(outer) if outer.name == "media" => {
+                outer.prelude = outer
+                    .prelude
+                    .iter()
+                    .cartesian_product(&queries)
+                    .map(|(outer, inner)| format!("{outer} and {inner}"))
+                    .collect();
+            }
+
+            _ => ctx.groups.push(Group {
+                name: String::from("media"),
+                prelude: queries,
+            }),
+        }
+
+        self.compile_block(&node.children, &ctx)
+    }
+
+    fn compile_layer(
+        &mut self,
+        node: &Node,
+        names: Vec<String>,
+        prelude_offset: usize,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        // a layer without any block is a statement that only
+        // declares the ordering of the layers
+        if node.children.is_empty() {
+            if names.is_empty() {
+                return Err(error_at(
+                    node,
+                    ErrorKind::InvalidAtRulePrelude,
+                    prelude_offset,
+                ));
+            }
+
+            let statement = AtRule {
+                name: String::from("layer"),
+                prelude: names.join(", "),
+                declarations: vec![],
+                items: None,
+            };
+
+            self.emit(Item::AtRule(statement), ctx);
+            return Ok(());
+        }
+
+        if names.len() > 1 {
+            return Err(error_at(
+                node,
+                ErrorKind::InvalidAtRulePrelude,
+                prelude_offset,
+            ));
+        }
+
+        let group = Group {
+            name: String::from("layer"),
+            prelude: names,
+        };
+
+        self.compile_group(node, group, ctx)
+    }
+
+    fn compile_extend(
+        &mut self,
+        node: &Node,
+        target: String,
+        is_optional: bool,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        if ctx.selectors.is_empty() {
+            return Err(error_at(node, ErrorKind::ExtendOutsideRule, 0));
+        }
+
+        if let Some(child) = node.children.first() {
+            return Err(error_at(child, ErrorKind::UnexpectedNestedRule, 0));
+        }
+
+        let groups = ctx
+            .groups
+            .iter()
+            .map(|group| (group.name.clone(), group.prelude.join(", ")))
+            .collect();
+
+        self.extensions.push(Extension {
+            extenders: ctx.selectors.clone(),
+            target,
+            groups,
+            is_optional,
+            row: node.row,
+            col: node.depth(),
+        });
+
+        Ok(())
+    }
+
+    fn compile_keyframes(
+        &mut self,
+        node: &Node,
+        name: &str,
+        keyframes_name: &str,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        let mut frames = vec![];
+
+        for frame in node.children.iter().filter(|frame| !frame.is_comment()) {
+            let selector = SmileyParser::parse(ParserRule::keyframe_selector, frame.content())
+                .map_err(|err| syntax_error(frame, ErrorKind::InvalidKeyframeSelector, 0, err))?
+                .next()
+                .unwrap();
+
+            let selectors = selector
+                .into_inner()
+                .filter(|pair| pair.as_rule() == ParserRule::keyframe_offset)
+                .map(|offset| offset.as_str().to_string())
+                .collect();
+
+            frames.push(Item::Rule(Rule {
+                selectors,
+                declarations: self.parse_declaration_block(frame)?,
+                pos: Some(frame.position_at(0)),
+            }));
+        }
+
+        let keyframes = AtRule {
+            name: name.to_string(),
+            prelude: keyframes_name.to_string(),
+            declarations: vec![],
+            items: Some(frames),
+        };
+
+        // the prefixed duplicates are only generated for the
+        // unprefixed keyframes
+        if name == "keyframes" {
+            let duplicates = self
+                .options
+                .keyframes_prefixes
+                .iter()
+                .map(|prefix| AtRule {
+                    name: format!("-{}-keyframes", prefix.trim_matches('-')),
+                    ..keyframes.clone()
+                })
+                .collect::<Vec<_>>();
+
+            for duplicate in duplicates {
+                self.emit(Item::AtRule(duplicate), ctx);
+            }
+        }
+
+        self.emit(Item::AtRule(keyframes), ctx);
+
+        Ok(())
+    }
+
+    fn compile_descriptor_block(
+        &mut self,
+        node: &Node,
+        name: &str,
+        prelude: &str,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        let mut declarations = vec![];
+        let mut margin_boxes = vec![];
+
+        for child in node.children.iter().filter(|child| !child.is_comment()) {
+            if let Some(margin_box) = child.content().strip_prefix('@') {
+                if name != "page" || !PAGE_MARGIN_BOXES.contains(&margin_box) {
+                    return Err(error_at(child, ErrorKind::UnknownAtRule, 0));
+                }
+
+                margin_boxes.push(Item::AtRule(AtRule {
+                    name: margin_box.to_string(),
+                    prelude: String::new(),
+                    declarations: self.parse_declaration_block(child)?,
+                    items: Some(vec![]),
+                }));
+
+                continue;
+            }
+
+            if child.kind == LineKind::Selector {
+                return Err(error_at(child, ErrorKind::UnexpectedNestedRule, 0));
+            }
+
+            declarations.extend(self.parse_declarations(child)?);
+        }
+
+        let required = REQUIRED_DESCRIPTORS
+            .iter()
+            .find(|(at_rule, _)| *at_rule == name)
+            .map_or(&[][..], |(_, descriptors)| descriptors);
+
+        for descriptor in required {
+            let is_missing = !declarations
+                .iter()
+                .any(|declaration| declaration.property.eq_ignore_ascii_case(descriptor));
+
+            if is_missing {
+                return Err(error_at(node, ErrorKind::MissingDescriptor(descriptor), 0));
+            }
+        }
+
+        let at_rule = AtRule {
+            name: name.to_string(),
+            prelude: prelude.to_string(),
+            declarations,
+            items: Some(margin_boxes),
+        };
+
+        self.emit(Item::AtRule(at_rule), ctx);
+
+        Ok(())
+    }
+
+    fn compile_group(&mut self, node: &Node, group: Group, ctx: &Context) -> Result<(), Error> {
+        let mut ctx = ctx.clone();
+        ctx.groups.push(group);
+
+        self.compile_block(&node.children, &ctx)
+    }
+
+    fn flush_declarations(&mut self, declarations: &mut Vec<Declaration>, ctx: &Context) {
+        if declarations.is_empty() {
+            return;
+        }
+
+        let rule = Rule {
+            selectors: ctx.selectors.clone(),
+            declarations: mem::take(declarations),
+            pos: ctx.pos,
+        };
+
+        self.emit(Item::Rule(rule), ctx);
+    }
+
+    /// Emit an item inside the groups of the context. The at-rules
+    /// that are not allowed inside a style rule (such as keyframes
+    /// and descriptor blocks) are bubbled up the same way as the
+    /// conditional groups.
+    fn emit(&mut self, item: Item, ctx: &Context) {
+        let mut items = &mut self.stylesheet.items;
+
+        // the groups are always bubbled up above the rules, so
+        // consecutive items within the same groups can share
+        // the same blocks
+        for group in &ctx.groups {
+            let prelude = group.prelude.join(", ");
+
+            let is_reusable = matches!(
+                items.last(),
+                Some(Item::AtRule(AtRule { name, prelude: p, items: Some(_), .. }))
+                    if *name == group.name && *p == prelude
+            );
+
+            if !is_reusable {
+                items.push(Item::AtRule(AtRule {
+                    name: group.name.clone(),
+                    prelude,
+                    declarations: vec![],
+                    items: Some(vec![]),
+                }));
+            }
+
+            items = match items.last_mut() {
+                Some(Item::AtRule(AtRule {
+                    items: Some(items), ..
+                })) => items,
+                _ => unreachable!(),
+            };
+        }
+
+        items.push(item);
+    }
+
+    /// Parse the children of a block that may only contain
+    /// declarations.
+    fn parse_declaration_block(&mut self, node: &Node) -> Result<Vec<Declaration>, Error> {
+        let mut declarations = vec![];
+
+        for child in node.children.iter().filter(|child| !child.is_comment()) {
+            if child.kind == LineKind::Selector || child.content().starts_with('@') {
+                return Err(error_at(child, ErrorKind::UnexpectedNestedRule, 0));
+            }
+
+            declarations.extend(self.parse_declarations(child)?);
+        }
+
+        Ok(declarations)
+    }
+
+    /// Parse a declaration, or expand a property namespace into
+    /// the declarations of its sub-properties.
+    fn parse_declarations(&mut self, node: &Node) -> Result<Vec<Declaration>, Error> {
+        match node.kind {
+            LineKind::PropertyNamespace => self.parse_property_namespace(node),
+            _ => Ok(vec![self.parse_declaration(node)?]),
+        }
+    }
+
+    /// Prefix the properties of the declarations inside a property
+    /// namespace, so that `family` inside `font:` becomes
+    /// `font-family`. The namespaces may be nested.
+    fn parse_property_namespace(&mut self, node: &Node) -> Result<Vec<Declaration>, Error> {
+        let content = node.content();
+
+        SmileyParser::parse(ParserRule::property_namespace, content)
+            .map_err(|err| syntax_error(node, ErrorKind::InvalidDeclaration, 0, err))?;
+
+        let namespace = content.trim_end_matches(':').trim_end();
+
+        let declarations = self
+            .parse_declaration_block(node)?
+            .into_iter()
+            .map(|declaration| Declaration {
+                property: format!("{namespace}-{}", declaration.property),
+                ..declaration
+            })
+            .collect();
+
+        Ok(declarations)
+    }
+
+    fn parse_declaration(&mut self, node: &Node) -> Result<Declaration, Error> {
+        let content = node.content();
+
+        // the errors are reported at the start of the declaration,
+        // rather than at the furthest position that pest attempted
+        let declaration = SmileyParser::parse(ParserRule::declaration, content)
+            .map_err(|_| error_at(node, ErrorKind::InvalidDeclaration, 0))?
+            .next()
+            .unwrap();
+
+        let mut property = String::new();
+        let mut expr = None;
+        let mut is_important = false;
+
+        for pair in declaration.into_inner() {
+            match pair.as_rule() {
+                ParserRule::property | ParserRule::custom_property_name => {
+                    property = pair.as_str().to_string();
+                }
+                // the values of custom properties are kept verbatim
+                ParserRule::custom_value => {
+                    return Ok(Declaration {
+                        property,
+                        value: pair.as_str().trim().to_string(),
+                        is_important: false,
+                        pos: Some(node.position_at(0)),
+                    });
+                }
+                ParserRule::expr => expr = Some(pair),
+                ParserRule::important => is_important = true,
+                _ => (),
+            }
+        }
+
+        // a successfully parsed declaration is guaranteed to have
+        // either a custom value or an expression
+        let expr = expr.unwrap();
+
+        // pest columns are counted in chars, starting from 1
+        let expr_offset = expr.as_span().start_pos().line_col().1 - 1;
+        self.warn_unknown_units(node, expr.as_str(), expr_offset);
+
+        Ok(Declaration {
+            property,
+            value: expression::evaluate(
+                node,
+                expr.as_str(),
+                expr_offset,
+                self.options.precision,
+                &self.variables,
+            )?,
+            is_important,
+            pos: Some(node.position_at(0)),
+        })
+    }
+
+    /// Warn about the units that are not in the unit table, as they
+    /// are passed through as is.
+    fn warn_unknown_units(&mut self, node: &Node, value: &str, offset: usize) {
+        for (unit, unit_offset) in find_unknown_units(value) {
+            let pos = node.position_at(offset + unit_offset);
+
+            warn!(
+                "Unknown unit `{unit}` at {}:{}, it will be passed through as is",
+                pos.row, pos.col
+            );
+
+            self.warnings.push(Warning {
+                kind: WarningKind::UnknownUnit(unit.to_string()),
+                pos,
+            });
+        }
+    }
+}
+
+fn parse_selector(node: &Node, parents: &[String]) -> Result<Vec<String>, Error> {
+    // the selectors are parsed atomically, so the syntax errors
+    // would be reported at the start of the compound selector
+    if let Some(col) = find_unterminated_bracket(node.content()) {
+        return Err(error_at(
+            node,
+            ErrorKind::UnterminatedAttributeSelector,
+            col,
+        ));
+    }
+
+    let selector = SmileyParser::parse(ParserRule::selector, node.content())
+        .map_err(|err| syntax_error(node, ErrorKind::InvalidSelector, 0, err))?
+        .next()
+        .unwrap();
+
+    let mut complex_selectors = vec![vec![]];
+
+    for part in selector.into_inner() {
+        match part.as_rule() {
+            ParserRule::list_separator => complex_selectors.push(vec![]),
+            ParserRule::EOI => (),
+            _ => complex_selectors.last_mut().unwrap().push(part),
+        }
+    }
+
+    if parents.is_empty() {
+        let parent_ref = complex_selectors
+            .iter()
+            .flatten()
+            .find(|part| part.as_str().starts_with('&'));
+
+        if let Some(part) = parent_ref {
+            // pest columns are counted in chars, starting from 1
+            let offset = part.as_span().start_pos().line_col().1 - 1;
+            return Err(error_at(node, ErrorKind::UnexpectedParentSelector, offset));
+        }
+
+        return Ok(complex_selectors
+            .iter()
+            .map(|parts| join_selector(parts, None))
+            .collect());
+    }
+
+    Ok(parents
+        .iter()
+        .cartesian_product(&complex_selectors)
+        .map(|(parent, parts)| join_selector(parts, Some(parent)))
+        .collect())
+}
+
+fn join_selector(parts: &[Pair<ParserRule>], parent: Option<&str>) -> String {
+    let mut selector = String::new();
+    let mut has_parent_ref = false;
+
+    for part in parts {
+        if part.as_rule() == ParserRule::combinator {
+            match part.as_str().trim() {
+                "" => selector.push(' '),
+                combinator => selector.push_str(&format!(" {combinator} ")),
+            }
+            continue;
+        }
+
+        match (part.as_str().strip_prefix('&'), parent) {
+            (Some(suffix), Some(parent)) => {
+                has_parent_ref = true;
+                selector.push_str(parent);
+                selector.push_str(suffix);
+            }
+            _ => selector.push_str(part.as_str()),
+        }
+    }
+
+    match parent {
+        Some(parent) if !has_parent_ref => format!("{parent} {selector}"),
+        _ => selector,
+    }
+}
+
+/// Find the units that are not in the unit table, along with
+/// their char offsets inside the value.
+fn find_unknown_units(value: &str) -> Vec<(&str, usize)> {
+    // the value has been validated as a part of the declaration
+    let pairs = SmileyParser::parse(ParserRule::declaration_value, value).unwrap();
+
+    pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == ParserRule::unit)
+        .filter(|pair| unit::category_of(pair.as_str()).is_none())
+        .map(|pair| (pair.as_str(), pair.as_span().start_pos().line_col().1 - 1))
+        .collect()
+}
+
+/// Find the char offset of an attribute selector bracket that is
+/// not closed.
+fn find_unterminated_bracket(selector: &str) -> Option<usize> {
+    let mut open = None;
+    let mut quote = None;
+    let mut chars = selector.chars().enumerate();
+
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (_, '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[') if open.is_none() => open = Some(i),
+            (None, ']') => open = None,
+            _ => (),
+        }
+    }
+
+    open
+}
+
+/// Remove the quotes around a string, so that the messages of
+/// the directives are printed as is.
+fn unquote(s: &str) -> &str {
+    for quote in ['"', '\''] {
+        let inner = s.strip_prefix(quote).and_then(|s| s.strip_suffix(quote));
+
+        if let Some(inner) = inner.filter(|inner| !inner.contains(quote)) {
+            return inner;
+        }
+    }
+
+    s
+}
+
+fn normalize_whitespaces(s: &str) -> String {
+    s.split_whitespace().join(" ")
+}
+
+fn error_at(node: &Node, kind: ErrorKind, offset: usize) -> Error {
+    Error {
+        kind,
+        pos: node.position_at(offset),
+    }
+}
+
+fn syntax_error(node: &Node, kind: ErrorKind, offset: usize, err: PestError<ParserRule>) -> Error {
+    let col = match err.line_col {
+        LineColLocation::Pos((_, col)) | LineColLocation::Span((_, col), _) => col,
+    };
+
+    // pest columns are starting from 1
+    error_at(node, kind, offset + col - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessor::{
+        line::{builder::Builder as LineBuilder, Line, NumberedLine},
+        tree,
+    };
+    use indoc::indoc;
+
+    #[test]
+    fn compile_nested_rules() {
+        let stylesheet = helpers::compile(indoc! {"
+            .card, .panel
+                color: red
+                .title
+                    font-size: 2em
+                &:hover, &-active
+                    color: blue
+                margin: 0 auto
+        "})
+        .unwrap();
+
+        let selectors = stylesheet
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::Rule(rule) => rule.selectors.join(", "),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            selectors,
+            vec![
+                ".card, .panel",
+                ".card .title, .panel .title",
+                ".card:hover, .card-active, .panel:hover, .panel-active",
+                ".card, .panel",
+            ]
+        );
+    }
+
+    #[test]
+    fn compile_property_namespaces() {
+        let stylesheet = helpers::compile(indoc! {"
+            .title
+                font:
+                    family: serif
+                    size: 2em
+                border:
+                    top:
+                        width: 1px
+                        style: solid
+                color: red
+        "})
+        .unwrap();
+
+        let declarations = match &stylesheet.items[..] {
+            [Item::Rule(rule)] => &rule.declarations,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            declarations,
+            &vec![
+                helpers::declaration("font-family", "serif"),
+                helpers::declaration("font-size", "2em"),
+                helpers::declaration("border-top-width", "1px"),
+                helpers::declaration("border-top-style", "solid"),
+                helpers::declaration("color", "red"),
+            ]
+        );
+    }
+
+    #[test]
+    fn compile_custom_properties() {
+        let stylesheet = helpers::compile(indoc! {"
+            :root
+                --brand-color:   #f00
+                --theme: { color: red; margin:  0 }
+                --url: https://x.y/z // note
+                -webkit-appearance: none
+                display: -webkit-box
+        "})
+        .unwrap();
+
+        let declarations = match &stylesheet.items[..] {
+            [Item::Rule(rule)] => &rule.declarations,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            declarations,
+            &vec![
+                helpers::declaration("--brand-color", "#f00"),
+                helpers::declaration("--theme", "{ color: red; margin:  0 }"),
+                helpers::declaration("--url", "https://x.y/z"),
+                helpers::declaration("-webkit-appearance", "none"),
+                helpers::declaration("display", "-webkit-box"),
+            ]
+        );
+    }
+
+    #[test]
+    fn compile_variables_with_flags() {
+        let stylesheet = helpers::compile(indoc! {"
+            $gap: 10px
+            $color: red
+            $color: blue !default
+            .a
+                $gap: 20px
+                $theme: dark !global
+                $size: 1em !default
+                margin: $gap * 2
+                color: $color ! important
+                font-size: $size
+            .b
+                margin: $gap
+                content: $theme
+        "})
+        .unwrap();
+
+        let declarations = stylesheet
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::Rule(rule) => rule.declarations.clone(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        let important = Declaration {
+            is_important: true,
+            ..helpers::declaration("color", "red")
+        };
+
+        assert_eq!(
+            declarations,
+            vec![
+                vec![
+                    helpers::declaration("margin", "40px"),
+                    important,
+                    helpers::declaration("font-size", "1em"),
+                ],
+                vec![
+                    helpers::declaration("margin", "10px"),
+                    helpers::declaration("content", "dark"),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn compile_global_variables() {
+        let stylesheet = helpers::compile(indoc! {"
+            $x: 1px
+            .a
+                $x: 2px !global
+                margin: $x
+            .b
+                margin: $x
+        "})
+        .unwrap();
+
+        let expected = [".a", ".b"]
+            .iter()
+            .map(|selector| {
+                Item::Rule(Rule {
+                    selectors: vec![selector.to_string()],
+                    declarations: vec![helpers::declaration("margin", "2px")],
+                    pos: None,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(stylesheet.items, expected);
+    }
+
+    #[test]
+    fn compile_with_defines() {
+        let mut lines = helpers::lines(indoc! {"
+            $gap: 10px !default
+            $color: red !default
+            .a
+                margin: $gap * 2
+                color: $color
+        "});
+        lines.push((0, Line::eof()));
+
+        let options = Options {
+            defines: vec![(String::from("gap"), String::from("4px"))],
+            ..Options::default()
+        };
+
+        let stylesheet = Compiler::new(options)
+            .compile(&tree::build(&lines))
+            .map(|(stylesheet, _)| helpers::without_positions(stylesheet))
+            .unwrap();
+
+        let expected = Item::Rule(Rule {
+            selectors: vec![String::from(".a")],
+            declarations: vec![
+                helpers::declaration("margin", "8px"),
+                helpers::declaration("color", "red"),
+            ],
+            pos: None,
+        });
+
+        assert_eq!(stylesheet.items, vec![expected]);
+
+        for value in ["1px )", "$gap", "1px !important", "1px + auto"] {
+            let options = Options {
+                defines: vec![(String::from("gap"), value.to_string())],
+                ..Options::default()
+            };
+
+            let err = Compiler::new(options)
+                .compile(&tree::build(&lines))
+                .unwrap_err();
+
+            assert_eq!(
+                err.kind,
+                ErrorKind::InvalidDefine(String::from("gap")),
+                "failed at `{value}`"
+            );
+        }
+    }
+
+    #[test]
+    fn compile_nested_media_queries() {
+        let stylesheet = helpers::compile(indoc! {"
+            .card
+                @media screen
+                    color: red
+                    @media (min-width: 600px)
+                        color: blue
+                    margin: 0
+        "})
+        .unwrap();
+
+        let queries = stylesheet
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::AtRule(at_rule) => at_rule.prelude.as_str(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            queries,
+            vec!["screen", "screen and (min-width: 600px)", "screen"]
+        );
+    }
+
+    #[test]
+    fn compile_nested_group_at_rules() {
+        let stylesheet = helpers::compile(indoc! {"
+            @layer base, components
+            @layer components
+                .card
+                    @supports (display: grid)
+                        display: grid
+                        @container sidebar (min-width: 400px)
+                            @scope (.card) to (.content)
+                                img
+                                    width: 100%
+        "})
+        .unwrap();
+
+        let expected = vec![
+            helpers::at_rule("layer", "base, components", None),
+            helpers::at_rule(
+                "layer",
+                "components",
+                Some(vec![helpers::at_rule(
+                    "supports",
+                    "(display: grid)",
+                    Some(vec![
+                        helpers::rule(".card", "display", "grid"),
+                        helpers::at_rule(
+                            "container",
+                            "sidebar (min-width: 400px)",
+                            Some(vec![helpers::at_rule(
+                                "scope",
+                                "(.card) to (.content)",
+                                Some(vec![helpers::rule(".card img", "width", "100%")]),
+                            )]),
+                        ),
+                    ]),
+                )]),
+            ),
+        ];
+
+        assert_eq!(stylesheet.items, expected);
+    }
+
+    #[test]
+    fn compile_nested_keyframes() {
+        let mut lines = helpers::lines(indoc! {"
+            .spinner
+                animation: spin 1s
+                @keyframes spin
+                    from
+                        transform: rotate(0deg)
+                    50%, 75%
+                        opacity: 0.5
+                    to
+                        transform: rotate(360deg)
+        "});
+        lines.push((0, Line::eof()));
+
+        let options = Options {
+            keyframes_prefixes: vec![String::from("webkit")],
+            ..Options::default()
+        };
+
+        let stylesheet = Compiler::new(options)
+            .compile(&tree::build(&lines))
+            .map(|(stylesheet, _)| helpers::without_positions(stylesheet))
+            .unwrap();
+
+        let frames = vec![
+            helpers::rule("from", "transform", "rotate(0deg)"),
+            Item::Rule(Rule {
+                selectors: vec![String::from("50%"), String::from("75%")],
+                declarations: vec![Declaration {
+                    property: String::from("opacity"),
+                    value: String::from("0.5"),
+                    is_important: false,
+                    pos: None,
+                }],
+                pos: None,
+            }),
+            helpers::rule("to", "transform", "rotate(360deg)"),
+        ];
+
+        let expected = vec![
+            helpers::rule(".spinner", "animation", "spin 1s"),
+            helpers::at_rule("-webkit-keyframes", "spin", Some(frames.clone())),
+            helpers::at_rule("keyframes", "spin", Some(frames)),
+        ];
+
+        assert_eq!(stylesheet.items, expected);
+    }
+
+    #[test]
+    fn compile_descriptor_blocks() {
+        let stylesheet = helpers::compile(indoc! {"
+            .card
+                @font-face
+                    font-family: \"Card Sans\"
+                    src: url(card.woff2) format(\"woff2\"), url(card.woff)
+                    unicode-range: U+0000-00FF, U+0131
+                @page wide:first
+                    margin: 1in
+                    @top-center
+                        content: counter(page)
+        "})
+        .unwrap();
+
+        let expected = vec![
+            Item::AtRule(AtRule {
+                name: String::from("font-face"),
+                prelude: String::new(),
+                declarations: vec![
+                    helpers::declaration("font-family", "\"Card Sans\""),
+                    helpers::declaration(
+                        "src",
+                        "url(card.woff2) format(\"woff2\"), url(card.woff)",
+                    ),
+                    helpers::declaration("unicode-range", "U+0000-00FF, U+0131"),
+                ],
+                items: Some(vec![]),
+            }),
+            Item::AtRule(AtRule {
+                name: String::from("page"),
+                prelude: String::from("wide:first"),
+                declarations: vec![helpers::declaration("margin", "1in")],
+                items: Some(vec![Item::AtRule(AtRule {
+                    name: String::from("top-center"),
+                    prelude: String::new(),
+                    declarations: vec![helpers::declaration("content", "counter(page)")],
+                    items: Some(vec![]),
+                })]),
+            }),
+        ];
+
+        assert_eq!(stylesheet.items, expected);
+    }
+
+    #[test]
+    fn compile_extensions() {
+        let stylesheet = helpers::compile(indoc! {"
+            %button
+                padding: 0
+            .alert
+                color: red
+            .primary
+                @extend %button
+                @extend .alert
+            @media print
+                .alert
+                    display: none
+                .link
+                    @extend .alert
+                    @extend .missing !optional
+        "})
+        .unwrap();
+
+        let selectors = |item: &Item| match item {
+            Item::Rule(rule) => rule.selectors.join(", "),
+            Item::AtRule(at_rule) => match &at_rule.items.as_ref().unwrap()[0] {
+                Item::Rule(rule) => format!("@{} {}", at_rule.name, rule.selectors.join(", ")),
+                _ => unreachable!(),
+            },
+            Item::Comment(_) => unreachable!(),
+        };
+
+        assert_eq!(
+            stylesheet.items.iter().map(selectors).collect::<Vec<_>>(),
+            vec![
+                ".primary",
+                ".alert, .primary",
+                "@media .alert, .primary, .link"
+            ]
+        );
+    }
+
+    #[test]
+    fn compile_with_error_in_continuation_line() {
+        let err = helpers::compile(indoc! {"
+            .a,
+              .b,
+              .c]
+                color: red
+        "})
+        .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::InvalidSelector);
+        assert_eq!((err.pos.row, err.pos.col), (3, 4));
+    }
+
+    #[test]
+    fn compile_unicode_identifiers() {
+        let stylesheet = helpers::compile(indoc! {"
+            .größe, .\\31 0col
+                &-klein
+                    color: red
+            .\\@sm\\:flex
+                display: flex
+        "})
+        .unwrap();
+
+        let selectors = stylesheet
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::Rule(rule) => rule.selectors.join(", "),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            selectors,
+            vec![".größe-klein, .\\31 0col-klein", ".\\@sm\\:flex"]
+        );
+    }
+
+    #[test]
+    fn compile_properties_with_escaped_colons() {
+        let stylesheet = helpers::compile(indoc! {"
+            .a
+                a\\:b: 1px
+                --a\\:b: 1px
+        "})
+        .unwrap();
+
+        let declarations = match &stylesheet.items[..] {
+            [Item::Rule(rule)] => &rule.declarations,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            declarations,
+            &vec![
+                helpers::declaration("a\\:b", "1px"),
+                helpers::declaration("--a\\:b", "1px"),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_unknown_units_in_values() {
+        assert_eq!(
+            find_unknown_units(" 1fr 100dvh 2foo calc(1bär + 10px)"),
+            vec![("foo", 13), ("bär", 23)]
+        );
+    }
+
+    #[test]
+    fn compile_with_errors() {
+        let cases = [
+            ("color: red", ErrorKind::DeclarationOutsideRule, 1, 0),
+            (
+                ".a\n    font:\n        .b\n            x: 0",
+                ErrorKind::UnexpectedNestedRule,
+                3,
+                8,
+            ),
+            ("a\n    color red", ErrorKind::InvalidDeclaration, 2, 4),
+            (
+                "a\n    width: 1px + 1em",
+                ErrorKind::IncompatibleUnits,
+                2,
+                15,
+            ),
+            ("a\n    width: $size", ErrorKind::UndefinedVariable, 2, 11),
+            (
+                "a\n    $min: 1px\n    @error 'Expected $min, got ' $min",
+                ErrorKind::UserError(String::from("'Expected $min, got ' 1px")),
+                3,
+                4,
+            ),
+            (
+                "@error \"Invalid size\"",
+                ErrorKind::UserError(String::from("Invalid size")),
+                1,
+                0,
+            ),
+            (
+                "a\n    $size 1px",
+                ErrorKind::InvalidVariableDeclaration,
+                2,
+                10,
+            ),
+            (
+                "a\n    $size: 1px !important",
+                ErrorKind::InvalidVariableDeclaration,
+                2,
+                16,
+            ),
+            (
+                "a\n    $a: 1px\nb\n    width: $a",
+                ErrorKind::UndefinedVariable,
+                4,
+                11,
+            ),
+            ("a >\n    color: red", ErrorKind::InvalidSelector, 1, 3),
+            (
+                "a, b[x\n    color: red",
+                ErrorKind::UnterminatedAttributeSelector,
+                1,
+                4,
+            ),
+            (
+                "a[x=']'\n    color: red",
+                ErrorKind::UnterminatedAttributeSelector,
+                1,
+                1,
+            ),
+            ("a[x]\\[\n    color: red", ErrorKind::InvalidSelector, 1, 4),
+            (".größe >\n    color: red", ErrorKind::InvalidSelector, 1, 8),
+            (
+                ".größe, &\n    color: red",
+                ErrorKind::UnexpectedParentSelector,
+                1,
+                8,
+            ),
+            (
+                "@media größe >\n    a\n        b: 0",
+                ErrorKind::InvalidMediaQuery,
+                1,
+                13,
+            ),
+            (
+                "&:hover\n    color: red",
+                ErrorKind::UnexpectedParentSelector,
+                1,
+                0,
+            ),
+            (
+                "@media\n    a\n        b: 0",
+                ErrorKind::InvalidMediaQuery,
+                1,
+                6,
+            ),
+            ("@foo\n    a\n        b: 0", ErrorKind::UnknownAtRule, 1, 0),
+            (
+                "@layer a, b\n    a\n        b: 0",
+                ErrorKind::InvalidAtRulePrelude,
+                1,
+                7,
+            ),
+            (
+                "@keyframes a\n    0\n        b: 0",
+                ErrorKind::InvalidKeyframeSelector,
+                2,
+                4,
+            ),
+            (
+                "@keyframes a\n    0%\n        b\n            c: 0",
+                ErrorKind::UnexpectedNestedRule,
+                3,
+                8,
+            ),
+            (
+                "@font-face\n    font-family: a",
+                ErrorKind::MissingDescriptor("src"),
+                1,
+                0,
+            ),
+            (
+                "@property --a\n    syntax: \"*\"\n    a\n        b: 0",
+                ErrorKind::UnexpectedNestedRule,
+                3,
+                4,
+            ),
+            ("@extend .a", ErrorKind::ExtendOutsideRule, 1, 0),
+            (".a\n    @extend .b", ErrorKind::ExtendTargetNotFound, 2, 4),
+            (
+                ".a\n    b: 0\n@media print\n    .b\n        @extend .a",
+                ErrorKind::ExtendAcrossMedia,
+                5,
+                8,
+            ),
+            (
+                "@supports display: grid\n    a\n        b: 0",
+                ErrorKind::InvalidAtRulePrelude,
+                1,
+                10,
+            ),
+        ];
+
+        for (src, kind, row, col) in cases {
+            let err = helpers::compile(src).unwrap_err();
+
+            assert_eq!(err.kind, kind, "failed at `{src}`");
+            assert_eq!((err.pos.row, err.pos.col), (row, col), "failed at `{src}`");
+        }
+    }
+
+    mod helpers {
+        use super::*;
+
+        pub fn lines(src: &str) -> Vec<NumberedLine> {
+            let mut builder = LineBuilder::default();
+            let mut lines = vec![];
+
+            for raw_line in src.lines() {
+                lines.extend(builder.build_line_from(raw_line).unwrap());
+            }
+
+            lines.extend(builder.finish().unwrap());
+            lines
+        }
+
+        pub fn compile(src: &str) -> Result<Stylesheet, Error> {
+            let mut lines = lines(src);
+            lines.push((0, Line::eof()));
+
+            Compiler::default()
+                .compile(&tree::build(&lines))
+                .map(|(stylesheet, _)| without_positions(stylesheet))
+        }
+
+        /// Remove the src positions from the compiled items, as
+        /// most of the tests are not concerned with them.
+        pub fn without_positions(stylesheet: Stylesheet) -> Stylesheet {
+            fn strip(items: Vec<Item>) -> Vec<Item> {
+                items
+                    .into_iter()
+                    .map(|item| match item {
+                        Item::Rule(rule) => Item::Rule(Rule {
+                            declarations: strip_declarations(rule.declarations),
+                            pos: None,
+                            ..rule
+                        }),
+                        Item::AtRule(at_rule) => Item::AtRule(AtRule {
+                            declarations: strip_declarations(at_rule.declarations),
+                            items: at_rule.items.map(strip),
+                            ..at_rule
+                        }),
+                        comment => comment,
+                    })
+                    .collect()
+            }
+
+            fn strip_declarations(declarations: Vec<Declaration>) -> Vec<Declaration> {
+                declarations
+                    .into_iter()
+                    .map(|declaration| Declaration {
+                        pos: None,
+                        ..declaration
+                    })
+                    .collect()
+            }
+
+            Stylesheet {
+                items: strip(stylesheet.items),
+            }
+        }
+
+        pub fn rule(selector: &str, property: &str, value: &str) -> Item {
+            Item::Rule(Rule {
+                selectors: vec![selector.to_string()],
+                declarations: vec![declaration(property, value)],
+                pos: None,
+            })
+        }
+
+        pub fn declaration(property: &str, value: &str) -> Declaration {
+            Declaration {
+                property: property.to_string(),
+                value: value.to_string(),
+                is_important: false,
+                pos: None,
+            }
+        }
+
+        pub fn at_rule(name: &str, prelude: &str, items: Option<Vec<Item>>) -> Item {
+            Item::AtRule(AtRule {
+                name: name.to_string(),
+                prelude: prelude.to_string(),
+                declarations: vec![],
+                items,
+            })
+        }
+    }
+}
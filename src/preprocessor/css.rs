@@ -0,0 +1,40 @@
+use super::line::position::Position;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Stylesheet {
+    pub items: Vec<Item>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Item {
+    Rule(Rule),
+    AtRule(AtRule),
+    Comment(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub selectors: Vec<String>,
+    pub declarations: Vec<Declaration>,
+    // the position in the src file, which is used by the source
+    // maps
+    pub pos: Option<Position>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Declaration {
+    pub property: String,
+    pub value: String,
+    pub is_important: bool,
+    pub pos: Option<Position>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtRule {
+    pub name: String,
+    pub prelude: String,
+    // descriptors of at-rules such as `@font-face`
+    pub declarations: Vec<Declaration>,
+    // statement at-rules (e.g. `@layer a, b;`) have no block
+    pub items: Option<Vec<Item>>,
+}
@@ -0,0 +1,506 @@
+use super::{tree::Node, unit, variables::Variables};
+use crate::{
+    error::{Error, ErrorKind},
+    parser::{Rule as ParserRule, SmileyParser},
+};
+use pest::{iterators::Pair, Parser};
+
+pub const DEFAULT_PRECISION: usize = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+}
+
+struct Number {
+    value: f64,
+    unit: String,
+}
+
+/// A variable that has been replaced by its value, with the
+/// char offsets of both the value and the original variable.
+struct Substitution {
+    col: usize,
+    len: usize,
+    original_col: usize,
+    original_len: usize,
+}
+
+/// Replace the variables inside an expression with their values,
+/// and then compute its arithmetic operations. The computed numbers
+/// are rounded into `precision` decimal places.
+///
+/// The `offset` is the char offset of the expression inside the
+/// node, which is used to locate the errors.
+pub fn evaluate(
+    node: &Node,
+    expr: &str,
+    offset: usize,
+    precision: usize,
+    variables: &Variables,
+) -> Result<String, Error> {
+    let mut evaluator = Evaluator {
+        node,
+        offset,
+        precision,
+        substitutions: vec![],
+    };
+
+    let expr = evaluator.substitute_variables(expr, variables)?;
+
+    // the expression has been validated as a part of a declaration,
+    // and the values of the variables are valid expressions
+    let expr = SmileyParser::parse(ParserRule::declaration_value, &expr)
+        .unwrap()
+        .flatten()
+        .find(|pair| pair.as_rule() == ParserRule::expr)
+        .unwrap();
+
+    evaluator.evaluate_expr(expr)
+}
+
+struct Evaluator<'a> {
+    node: &'a Node,
+    offset: usize,
+    precision: usize,
+    substitutions: Vec<Substitution>,
+}
+
+impl Evaluator<'_> {
+    fn substitute_variables(&mut self, expr: &str, variables: &Variables) -> Result<String, Error> {
+        let pairs = SmileyParser::parse(ParserRule::declaration_value, expr).unwrap();
+        let mut result = String::new();
+        let mut last = 0;
+
+        for pair in pairs.flatten() {
+            // the variables of the signed terms are substituted along
+            // with their signs
+            let (sign, variable) = match pair.as_rule() {
+                ParserRule::signed => {
+                    let mut inner = pair.clone().into_inner();
+                    let sign = inner.next().unwrap().as_str();
+                    (Some(sign), inner.next().unwrap())
+                }
+                ParserRule::variable if pair.as_span().start() >= last => (None, pair.clone()),
+                _ => continue,
+            };
+
+            if variable.as_rule() != ParserRule::variable {
+                continue;
+            }
+
+            let span = pair.as_span();
+            let original_col = span.start_pos().line_col().1 - 1;
+            let pos = self.node.position_at(self.offset + original_col);
+
+            let error = |kind| Error { kind, pos };
+
+            let value = variables
+                .get(&variable.as_str()[1..])
+                .ok_or_else(|| error(ErrorKind::UndefinedVariable))?;
+
+            let value = match sign {
+                Some(sign) => {
+                    apply_sign(sign, value).ok_or_else(|| error(ErrorKind::InvalidOperand))?
+                }
+                None => value.to_string(),
+            };
+
+            result.push_str(&expr[last..span.start()]);
+
+            self.substitutions.push(Substitution {
+                col: result.chars().count(),
+                len: value.chars().count(),
+                original_col,
+                original_len: pair.as_str().chars().count(),
+            });
+
+            result.push_str(&value);
+            last = span.end();
+        }
+
+        result.push_str(&expr[last..]);
+
+        Ok(result)
+    }
+
+    fn evaluate_expr(&self, expr: Pair<ParserRule>) -> Result<String, Error> {
+        let mut result = String::new();
+        let mut operands = vec![];
+        let mut operators = vec![];
+
+        for pair in expr.into_inner() {
+            if pair.as_rule() == ParserRule::term {
+                operands.push(pair);
+                continue;
+            }
+
+            let operator = match pair.as_str().trim() {
+                "+" => Operator::Add,
+                "-" => Operator::Subtract,
+                "*" => Operator::Multiply,
+                // the other operators are separating the values
+                // of a list
+                _ => {
+                    result.push_str(&self.evaluate_operation(&operands, &operators)?);
+                    result.push_str(pair.as_str());
+
+                    operands.clear();
+                    operators.clear();
+                    continue;
+                }
+            };
+
+            operators.push((operator, pair));
+        }
+
+        result.push_str(&self.evaluate_operation(&operands, &operators)?);
+
+        Ok(result)
+    }
+
+    fn evaluate_operation(
+        &self,
+        operands: &[Pair<ParserRule>],
+        operators: &[(Operator, Pair<ParserRule>)],
+    ) -> Result<String, Error> {
+        if operators.is_empty() {
+            return Ok(operands.iter().map(format_term).collect());
+        }
+
+        let numbers = operands
+            .iter()
+            .map(|term| self.parse_number(term))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut numbers = numbers.into_iter();
+        let mut sums = vec![(Operator::Add, numbers.next().unwrap(), None)];
+
+        // the multiplications are computed first, as they have a
+        // higher precedence than the additions and subtractions
+        for ((operator, pair), number) in operators.iter().zip(numbers) {
+            if *operator != Operator::Multiply {
+                sums.push((*operator, number, Some(pair)));
+                continue;
+            }
+
+            let (_, product, _) = sums.last_mut().unwrap();
+            *product = self.multiply(product, number, pair)?;
+        }
+
+        let mut sums = sums.into_iter();
+        let (_, mut total, _) = sums.next().unwrap();
+
+        for (operator, number, pair) in sums {
+            let sign = if operator == Operator::Subtract {
+                -1.0
+            } else {
+                1.0
+            };
+            total = self.add(total, number, sign, pair.unwrap())?;
+        }
+
+        if !total.value.is_finite() {
+            return Err(self.error_at(&operands[0], ErrorKind::NumberOutOfRange));
+        }
+
+        Ok(self.format_number(&total))
+    }
+
+    fn parse_number(&self, term: &Pair<ParserRule>) -> Result<Number, Error> {
+        let inner = term.clone().into_inner().next();
+        let text = term.as_str();
+
+        let (value, unit) = match inner.as_ref().map(|inner| inner.as_rule()) {
+            Some(ParserRule::number) => (text, ""),
+            Some(ParserRule::percentage) => (&text[..text.len() - 1], "%"),
+            Some(ParserRule::dimension) => {
+                let mut parts = inner.unwrap().into_inner();
+                let value = parts.next().unwrap().as_str();
+                (value, parts.next().unwrap().as_str())
+            }
+            _ => return Err(self.error_at(term, ErrorKind::InvalidOperand)),
+        };
+
+        Ok(Number {
+            value: value.parse().unwrap(),
+            unit: unit.to_string(),
+        })
+    }
+
+    fn multiply(
+        &self,
+        lhs: &Number,
+        rhs: Number,
+        pair: &Pair<ParserRule>,
+    ) -> Result<Number, Error> {
+        // a product of two dimensions (such as `px * px`) can't
+        // be represented in CSS
+        let unit = match (lhs.unit.as_str(), rhs.unit.as_str()) {
+            ("", unit) | (unit, "") => unit.to_string(),
+            _ => return Err(self.error_at(pair, ErrorKind::IncompatibleUnits)),
+        };
+
+        Ok(Number {
+            value: lhs.value * rhs.value,
+            unit,
+        })
+    }
+
+    /// Add the `rhs` into `lhs`, in the unit of the `lhs`.
+    fn add(
+        &self,
+        lhs: Number,
+        rhs: Number,
+        sign: f64,
+        pair: &Pair<ParserRule>,
+    ) -> Result<Number, Error> {
+        let (factor, unit) = match (lhs.unit.as_str(), rhs.unit.as_str()) {
+            (_, "") => (Some(1.0), lhs.unit.clone()),
+            ("", _) => (Some(1.0), rhs.unit.clone()),
+            (to, from) => (unit::conversion_factor(from, to), lhs.unit.clone()),
+        };
+
+        let factor = factor.ok_or_else(|| self.error_at(pair, ErrorKind::IncompatibleUnits))?;
+
+        Ok(Number {
+            value: lhs.value + sign * rhs.value * factor,
+            unit,
+        })
+    }
+
+    fn format_number(&self, number: &Number) -> String {
+        // only 15 significant digits of a double are exact, the rest
+        // are the artifacts of its binary representation
+        let value = format!("{:.14e}", number.value).parse::<f64>().unwrap();
+        let value = format!("{:.*}", self.precision, value)
+            .parse::<f64>()
+            .unwrap();
+
+        // the shortest representation that reads back into the same
+        // value, which is never written with an exponent
+        let mut value = value.to_string();
+
+        if value == "-0" {
+            value = String::from("0");
+        }
+
+        format!("{value}{}", number.unit)
+    }
+
+    fn error_at(&self, pair: &Pair<ParserRule>, kind: ErrorKind) -> Error {
+        // pest columns are counted in chars, starting from 1, and
+        // the operators may be preceded by whitespaces
+        let text = pair.as_str();
+        let col =
+            pair.as_span().start_pos().line_col().1 - 1 + text.len() - text.trim_start().len();
+
+        Error {
+            kind,
+            pos: self.node.position_at(self.offset + self.original_col(col)),
+        }
+    }
+
+    /// Map a column of the substituted expression into the column
+    /// of the original expression.
+    fn original_col(&self, col: usize) -> usize {
+        let mut original_col = col;
+
+        for substitution in &self.substitutions {
+            if col < substitution.col {
+                break;
+            }
+
+            // errors inside the value of a variable are reported
+            // at the variable
+            if col < substitution.col + substitution.len {
+                return substitution.original_col;
+            }
+
+            original_col = substitution.original_col
+                + substitution.original_len
+                + (col - substitution.col - substitution.len);
+        }
+
+        original_col
+    }
+}
+
+/// Apply the sign of a signed variable to its value, which should
+/// be a single number, percentage or dimension.
+fn apply_sign(sign: &str, value: &str) -> Option<String> {
+    let expr = SmileyParser::parse(ParserRule::declaration_value, value)
+        .ok()?
+        .flatten()
+        .find(|pair| pair.as_rule() == ParserRule::expr)?;
+
+    let mut terms = expr.into_inner();
+    let term = terms.next()?.into_inner().next()?;
+
+    let is_number = matches!(
+        term.as_rule(),
+        ParserRule::number | ParserRule::percentage | ParserRule::dimension
+    );
+
+    if !is_number || terms.next().is_some() {
+        return None;
+    }
+
+    let (is_negative, magnitude) = match value.strip_prefix('-') {
+        Some(magnitude) => (true, magnitude),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+
+    if is_negative == (sign == "-") {
+        Some(magnitude.to_string())
+    } else {
+        Some(format!("-{magnitude}"))
+    }
+}
+
+/// Get the text of a term, without the trailing dot of its
+/// number (e.g. `1.px` becomes `1px`).
+fn format_term(term: &Pair<ParserRule>) -> String {
+    let text = term.as_str();
+
+    let number = match term.clone().into_inner().next() {
+        Some(inner) => match inner.as_rule() {
+            ParserRule::number | ParserRule::percentage => text.trim_end_matches('%'),
+            ParserRule::dimension => inner.into_inner().next().unwrap().as_str(),
+            _ => text,
+        },
+        None => text,
+    };
+
+    match number.strip_suffix('.') {
+        Some(number) => format!("{number}{}", &text[number.len() + 1..]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessor::line::{indentation::Indentation, Content, Line, LineKind};
+
+    #[test]
+    fn evaluate_arithmetic_operations() {
+        let cases = [
+            ("1px solid red", "1px solid red"),
+            ("10px + 5px", "15px"),
+            ("10px - 5px", "5px"),
+            ("10px -5px", "10px -5px"),
+            ("10px - -5px", "15px"),
+            ("2 + 3 * 4", "14"),
+            ("2*3em", "6em"),
+            ("1in + 1px", "1.0104166667in"),
+            ("1s - 500ms", "0.5s"),
+            ("50% + 10", "60%"),
+            ("1e3px + 1.5E-2px", "1000.015px"),
+            ("1.px .5em 1.%", "1px .5em 1%"),
+            ("0 auto, 1px + 1px 0", "0 auto, 2px 0"),
+            ("16px/1.5 serif", "16px/1.5 serif"),
+            ("0.1 + 0.2", "0.3"),
+            ("1px - 1px", "0px"),
+            ("0.00000000001 - 0.00000000002", "0"),
+            ("1e20 * 1e5", "10000000000000000000000000"),
+            ("1e-7px * 1", "0.0000001px"),
+            ("calc(1px + 1em)", "calc(1px + 1em)"),
+        ];
+
+        for (value, expected) in cases {
+            let node = helpers::node(value);
+            let result =
+                evaluate(&node, value, 0, DEFAULT_PRECISION, &Variables::default()).unwrap();
+
+            assert_eq!(result, expected, "failed at `{value}`");
+        }
+    }
+
+    #[test]
+    fn evaluate_with_precision() {
+        let node = helpers::node("10px * 0.33333");
+
+        assert_eq!(
+            evaluate(&node, "10px * 0.33333", 0, 2, &Variables::default()).unwrap(),
+            "3.33px"
+        );
+        assert_eq!(
+            evaluate(&node, "10px * 0.33333", 0, 0, &Variables::default()).unwrap(),
+            "3px"
+        );
+    }
+
+    #[test]
+    fn evaluate_with_errors() {
+        let cases = [
+            ("1px + 1em", ErrorKind::IncompatibleUnits, 4),
+            ("1px * 2px", ErrorKind::IncompatibleUnits, 4),
+            ("1px + 1deg", ErrorKind::IncompatibleUnits, 4),
+            ("1px + auto", ErrorKind::InvalidOperand, 6),
+            ("'a' + 'b'", ErrorKind::InvalidOperand, 0),
+            ("1e400 + 1", ErrorKind::NumberOutOfRange, 0),
+            ("2 * 1e308px", ErrorKind::NumberOutOfRange, 0),
+        ];
+
+        for (value, kind, col) in cases {
+            let node = helpers::node(value);
+            let err =
+                evaluate(&node, value, 0, DEFAULT_PRECISION, &Variables::default()).unwrap_err();
+
+            assert_eq!(err.kind, kind, "failed at `{value}`");
+            assert_eq!((err.pos.row, err.pos.col), (1, col), "failed at `{value}`");
+        }
+    }
+
+    #[test]
+    fn evaluate_with_variables() {
+        let mut variables = Variables::default();
+        variables.push_scope();
+        variables.set("gap", String::from("10px"), false);
+        variables.set("color", String::from("#fff"), false);
+        variables.set("neg", String::from("-5%"), false);
+
+        let cases = [
+            ("$gap * 2 solid $color", Ok("20px solid #fff")),
+            ("-$gap", Ok("-10px")),
+            ("10px - -$gap", Ok("20px")),
+            ("10px -$gap +$gap", Ok("10px -10px 10px")),
+            ("-$neg", Ok("5%")),
+            ("-max($gap, 1em)", Ok("-max(10px, 1em)")),
+            ("+max($gap, 1em)", Ok("+max(10px, 1em)")),
+            ("1px -$color", Err((ErrorKind::InvalidOperand, 4))),
+            ("rgba($color, .5)", Ok("rgba(#fff, .5)")),
+            ("$size", Err((ErrorKind::UndefinedVariable, 0))),
+            ("$color + 1", Err((ErrorKind::InvalidOperand, 0))),
+            ("$gap + 1em", Err((ErrorKind::IncompatibleUnits, 5))),
+        ];
+
+        for (value, expected) in cases {
+            let node = helpers::node(value);
+            let result = evaluate(&node, value, 0, DEFAULT_PRECISION, &variables)
+                .map_err(|err| (err.kind, err.pos.col));
+
+            assert_eq!(result, expected.map(String::from), "failed at `{value}`");
+        }
+    }
+
+    mod helpers {
+        use super::*;
+
+        pub fn node(value: &str) -> Node {
+            Node {
+                row: 1,
+                line: Line {
+                    content: Content::Value(value.to_string()),
+                    indentation: Indentation::none(),
+                    continuations: vec![],
+                },
+                kind: LineKind::Declaration,
+                children: vec![],
+            }
+        }
+    }
+}
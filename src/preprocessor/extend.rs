@@ -0,0 +1,239 @@
+use super::{
+    css::Item,
+    line::position::{Col, Position, Row},
+};
+use crate::error::{Error, ErrorKind};
+
+/// The name and prelude of each conditional group (such as
+/// `@media`) enclosing a rule.
+pub type GroupPath = Vec<(String, String)>;
+
+pub struct Extension {
+    pub extenders: Vec<String>,
+    pub target: String,
+    pub groups: GroupPath,
+    pub is_optional: bool,
+    pub row: Row,
+    pub col: Col,
+}
+
+#[derive(Clone, Copy, Default)]
+struct MatchState {
+    in_same_groups: bool,
+    in_other_groups: bool,
+}
+
+/// Append the extending selectors into every rule matching the
+/// extension targets, and then remove the rules that are only
+/// targeted by placeholder selectors.
+pub fn apply(mut items: Vec<Item>, extensions: &[Extension]) -> Result<Vec<Item>, Error> {
+    let mut states = vec![MatchState::default(); extensions.len()];
+
+    extend_items(&mut items, &mut vec![], extensions, &mut states);
+
+    for (extension, state) in extensions.iter().zip(&states) {
+        if extension.is_optional || state.in_same_groups {
+            continue;
+        }
+
+        let kind = if state.in_other_groups {
+            ErrorKind::ExtendAcrossMedia
+        } else {
+            ErrorKind::ExtendTargetNotFound
+        };
+
+        return Err(Error {
+            kind,
+            pos: Position::at(extension.row, extension.col),
+        });
+    }
+
+    Ok(remove_placeholders(items))
+}
+
+fn extend_items(
+    items: &mut [Item],
+    path: &mut GroupPath,
+    extensions: &[Extension],
+    states: &mut [MatchState],
+) {
+    for item in items {
+        match item {
+            Item::Rule(rule) => extend_selectors(&mut rule.selectors, path, extensions, states),
+
+            Item::AtRule(at_rule) => {
+                if let Some(items) = &mut at_rule.items {
+                    path.push((at_rule.name.clone(), at_rule.prelude.clone()));
+                    extend_items(items, path, extensions, states);
+                    path.pop();
+                }
+            }
+
+            Item::Comment(_) => (),
+        }
+    }
+}
+
+fn extend_selectors(
+    selectors: &mut Vec<String>,
+    path: &[(String, String)],
+    extensions: &[Extension],
+    states: &mut [MatchState],
+) {
+    // the newly added selectors are also checked, so that the
+    // extensions can be chained
+    let mut i = 0;
+
+    while i < selectors.len() {
+        let selector = selectors[i].clone();
+        i += 1;
+
+        for (extension, state) in extensions.iter().zip(states.iter_mut()) {
+            if !contains_simple_selector(&selector, &extension.target) {
+                continue;
+            }
+
+            // extensions from outside of any group are applied
+            // to every rule, while the others may only extend
+            // rules within the same groups
+            if !extension.groups.is_empty() && extension.groups != path {
+                state.in_other_groups = true;
+                continue;
+            }
+
+            state.in_same_groups = true;
+
+            for extender in &extension.extenders {
+                if contains_simple_selector(extender, &extension.target) {
+                    continue;
+                }
+
+                let extended = replace_simple_selector(&selector, &extension.target, extender);
+
+                if !selectors.contains(&extended) {
+                    selectors.push(extended);
+                }
+            }
+        }
+    }
+}
+
+fn remove_placeholders(items: Vec<Item>) -> Vec<Item> {
+    items
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Rule(mut rule) => {
+                rule.selectors.retain(|selector| !has_placeholder(selector));
+
+                if rule.selectors.is_empty() {
+                    None
+                } else {
+                    Some(Item::Rule(rule))
+                }
+            }
+
+            Item::AtRule(mut at_rule) => match at_rule.items.take() {
+                // only remove the blocks that became empty because
+                // all of their rules were removed
+                Some(items) if !items.is_empty() => {
+                    let items = remove_placeholders(items);
+
+                    if items.is_empty() && at_rule.declarations.is_empty() {
+                        return None;
+                    }
+
+                    at_rule.items = Some(items);
+                    Some(Item::AtRule(at_rule))
+                }
+
+                items => {
+                    at_rule.items = items;
+                    Some(Item::AtRule(at_rule))
+                }
+            },
+
+            comment => Some(comment),
+        })
+        .collect()
+}
+
+fn find_simple_selector(selector: &str, target: &str, from: usize) -> Option<usize> {
+    let mut start = from;
+
+    while let Some(offset) = selector[start..].find(target) {
+        let pos = start + offset;
+        let end = pos + target.len();
+
+        // make sure that `.foo` won't match `.foobar`
+        let is_boundary = selector[end..]
+            .chars()
+            .next()
+            .map_or(true, |c| !is_ident_char(c));
+
+        if is_boundary {
+            return Some(pos);
+        }
+
+        start = end;
+    }
+
+    None
+}
+
+fn contains_simple_selector(selector: &str, target: &str) -> bool {
+    find_simple_selector(selector, target, 0).is_some()
+}
+
+fn replace_simple_selector(selector: &str, target: &str, replacement: &str) -> String {
+    let mut result = String::new();
+    let mut last = 0;
+
+    while let Some(pos) = find_simple_selector(selector, target, last) {
+        result.push_str(&selector[last..pos]);
+        result.push_str(replacement);
+        last = pos + target.len();
+    }
+
+    result.push_str(&selector[last..]);
+    result
+}
+
+fn has_placeholder(selector: &str) -> bool {
+    selector.match_indices('%').any(|(pos, _)| {
+        selector[pos + 1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+    })
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '\\' || !c.is_ascii()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simple_selector_replacement() {
+        let cases = [
+            (".foo", ".foo", ".bar", ".bar"),
+            (".a .foo:hover", ".foo", ".b", ".a .b:hover"),
+            (".foobar .foo", ".foo", ".b", ".foobar .b"),
+            (".fooß .foo", ".foo", ".b", ".fooß .b"),
+            ("%base.foo", "%base", "button", "button.foo"),
+        ];
+
+        for (selector, target, replacement, expected) in cases {
+            assert_eq!(
+                replace_simple_selector(selector, target, replacement),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn placeholder_detection() {
+        assert!(has_placeholder("%base"));
+        assert!(has_placeholder(".a %base:hover"));
+        assert!(!has_placeholder(".a[data-width=\"50%\"]"));
+    }
+}
@@ -1,7 +1,8 @@
 use indentation::Indentation;
-use position::Row;
+use position::{Position, Row};
 
 pub mod builder;
+pub mod comment;
 pub mod indentation;
 pub mod position;
 
@@ -11,6 +12,17 @@ pub type NumberedLine = (Row, Line);
 pub struct Line {
     pub content: Content,
     pub indentation: Indentation,
+    pub continuations: Vec<Continuation>,
+}
+
+/// A physical line that has been joined into the preceding line
+/// to form a single logical line.
+#[derive(Clone, Debug)]
+pub struct Continuation {
+    // the char offset inside the content of the logical line
+    pub offset: usize,
+    // the position of the first non-whitespace char
+    pub pos: Position,
 }
 
 impl Line {
@@ -18,30 +30,57 @@ impl Line {
         Self {
             content: Content::Eof,
             indentation: Indentation::none(),
+            continuations: vec![],
+        }
+    }
+
+    pub fn comment(text: String) -> Self {
+        Self {
+            content: Content::Comment(text),
+            // the indentation of the comments are ignored
+            indentation: Indentation::none(),
+            continuations: vec![],
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        match &self.content {
+            Content::Value(value) | Content::Comment(value) => value,
+            Content::Eof => "",
         }
     }
+
+    pub fn is_comment(&self) -> bool {
+        matches!(self.content, Content::Comment(_))
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum Content {
     Eof,
     Value(String),
+    Comment(String),
 }
 
 #[derive(Debug, PartialEq)]
 pub enum LineKind {
     Selector,
     Declaration,
+    // a property without value (such as `font:`), that contains
+    // the declarations of its sub-properties
+    PropertyNamespace,
 }
 
 pub fn determine_kind(line: &Line, next_line: &Line) -> LineKind {
     let level = line.indentation.depth;
     let next_level = next_line.indentation.depth;
 
-    if level < next_level {
-        LineKind::Selector
-    } else {
+    if level >= next_level {
         LineKind::Declaration
+    } else if line.value().ends_with(':') && !line.value().ends_with("\\:") {
+        LineKind::PropertyNamespace
+    } else {
+        LineKind::Selector
     }
 }
 
@@ -101,6 +140,21 @@ mod tests {
         assert!(pass);
     }
 
+    #[test]
+    fn property_namespace_classification() {
+        let namespace = helpers::line_from("font:", 2);
+        let declaration = helpers::line_from("family: serif", 4);
+
+        assert_eq!(
+            determine_kind(&namespace, &declaration),
+            LineKind::PropertyNamespace
+        );
+        assert_eq!(
+            determine_kind(&declaration, &Line::eof()),
+            LineKind::Declaration
+        );
+    }
+
     mod helpers {
         use super::*;
         use crate::preprocessor::line::indentation::{Indentation, IndentationKind};
@@ -114,6 +168,7 @@ mod tests {
             Line {
                 content: Content::Value(String::from(s)),
                 indentation: Indentation { kind, depth },
+                continuations: vec![],
             }
         }
     }
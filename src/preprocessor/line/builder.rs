@@ -1,7 +1,8 @@
 use super::{
+    comment,
     indentation::{Indentation, IndentationKind},
-    position::{Position, Row},
-    Content as LineContent, Line, NumberedLine,
+    position::{Col, Position, Row},
+    Content as LineContent, Continuation, Line, NumberedLine,
 };
 use crate::error::{Error, ErrorKind};
 use log::debug;
@@ -10,16 +11,91 @@ use log::debug;
 pub struct Builder {
     row_count: Row,
     indentation_handler: IndentationHandler,
+    open_comment: Option<OpenComment>,
+    open_line: Option<NumberedLine>,
+    // a built line that is held back, as it may still be continued
+    // by the next physical line
+    pending_line: Option<NumberedLine>,
+}
+
+// a block comment spanning multiple lines, that has not been
+// closed yet
+struct OpenComment {
+    row: Row,
+    col: Col,
+    text: String,
 }
 
 impl Builder {
-    pub fn build_line_from(&mut self, raw_line: &str) -> Result<Option<NumberedLine>, Error> {
+    /// Build the logical lines that are completed by a physical
+    /// line. A block comment placed on a line with code is built
+    /// into a separate line, right before the line of the code.
+    pub fn build_line_from(&mut self, raw_line: &str) -> Result<Vec<NumberedLine>, Error> {
         self.row_count += 1;
 
-        if raw_line.trim().is_empty() {
-            return Ok(None);
+        if self.open_comment.is_some() {
+            return Ok(self.continue_comment(raw_line)?.into_iter().collect());
+        }
+
+        let split = comment::split(raw_line);
+
+        if split.code.trim().is_empty() && self.open_line.is_none() {
+            let comment = self.start_comment(split);
+
+            if comment.is_none() && self.open_comment.is_none() {
+                return Ok(vec![]);
+            }
+
+            // a line held back can't be continued after a block comment
+            return Ok(self
+                .pending_line
+                .take()
+                .into_iter()
+                .chain(comment)
+                .collect());
+        }
+
+        if let Some(col) = split.unclosed_at {
+            return Err(Error {
+                kind: ErrorKind::UnterminatedComment,
+                pos: Position::at(self.row_count, col),
+            });
+        }
+
+        // comments in between continuation lines are placed right
+        // before the continued line
+        if split.code.trim().is_empty() {
+            return Ok(self.start_comment(split).into_iter().collect());
+        }
+
+        let (row, mut line, ready) = match (self.open_line.take(), self.pending_line.take()) {
+            (Some((row, line)), _) => (row, self.continue_line(line, &split.code), None),
+            (None, Some((row, line))) if is_continued_by_string(&line, &split.code) => {
+                (row, self.continue_line(line, &split.code), None)
+            }
+            (None, pending) => (self.row_count, self.start_line(&split.code)?, pending),
+        };
+
+        let mut lines = ready.into_iter().collect::<Vec<_>>();
+        lines.extend(self.start_comment(split));
+
+        if is_continued(line.value()) {
+            self.open_line = Some((row, line));
+            return Ok(lines);
+        }
+
+        line.content = LineContent::Value(line.value().trim_end().to_string());
+
+        if accepts_string_continuation(line.value()) {
+            self.pending_line = Some((row, line));
+        } else {
+            lines.push((row, line));
         }
 
+        Ok(lines)
+    }
+
+    fn start_line(&mut self, raw_line: &str) -> Result<Line, Error> {
         let indent = Indentation::of_line(raw_line).map_err(|col| Error {
             kind: ErrorKind::InconsistentIndentation,
             pos: Position::at(self.row_count, col),
@@ -32,16 +108,152 @@ impl Builder {
                 pos: Position::at(self.row_count, 0),
             })?;
 
-        Ok(Some((
-            self.row_count,
-            Line {
-                // remove indentations, and put the information
-                // inside indentation_mode instead
-                content: LineContent::Value(raw_line.trim().to_string()),
-                indentation: indent,
-            },
-        )))
+        Ok(Line {
+            // remove indentations, and put the information
+            // inside indentation_mode instead
+            content: LineContent::Value(raw_line.trim().to_string()),
+            indentation: indent,
+            continuations: vec![],
+        })
+    }
+
+    /// Join a physical line into a logical line. The indentation
+    /// of the joined line is ignored.
+    fn continue_line(&self, mut line: Line, raw_line: &str) -> Line {
+        let mut content = line.value().trim_end().to_string();
+        let joined = raw_line.trim();
+
+        if !content.ends_with('(') && !joined.starts_with(')') {
+            content.push(' ');
+        }
+
+        let indent_len = raw_line.chars().take_while(|c| c.is_whitespace()).count();
+
+        line.continuations.push(Continuation {
+            offset: content.chars().count(),
+            pos: Position::at(self.row_count, indent_len),
+        });
+
+        content.push_str(joined);
+        line.content = LineContent::Value(content);
+
+        line
+    }
+
+    /// Make sure that there is no unclosed block comment left
+    /// after all of the lines have been built, returning the line
+    /// that is still held back (if any).
+    pub fn finish(&mut self) -> Result<Option<NumberedLine>, Error> {
+        if let Some(comment) = &self.open_comment {
+            return Err(Error {
+                kind: ErrorKind::UnterminatedComment,
+                pos: Position::at(comment.row, comment.col),
+            });
+        }
+
+        if self.open_line.is_some() {
+            return Err(Error {
+                kind: ErrorKind::UnexpectedEof,
+                pos: Position::at(self.row_count, 0),
+            });
+        }
+
+        Ok(self.pending_line.take())
+    }
+
+    fn start_comment(&mut self, split: comment::SplitLine) -> Option<NumberedLine> {
+        if split.comment.is_empty() {
+            return None;
+        }
+
+        if let Some(col) = split.unclosed_at {
+            self.open_comment = Some(OpenComment {
+                row: self.row_count,
+                col,
+                text: split.comment,
+            });
+
+            return None;
+        }
+
+        Some((self.row_count, Line::comment(split.comment)))
+    }
+
+    fn continue_comment(&mut self, raw_line: &str) -> Result<Option<NumberedLine>, Error> {
+        let comment = self.open_comment.as_mut().unwrap();
+        comment.text.push('\n');
+
+        let end = match raw_line.find("*/") {
+            Some(end) => end + 2,
+            None => {
+                comment.text.push_str(raw_line.trim_end());
+                return Ok(None);
+            }
+        };
+
+        comment.text.push_str(&raw_line[..end]);
+
+        let rest = comment::split(&raw_line[end..]);
+
+        if !rest.code.trim().is_empty() || !rest.comment.is_empty() {
+            return Err(Error {
+                kind: ErrorKind::UnexpectedCodeAfterComment,
+                pos: Position::at(self.row_count, raw_line[..end].chars().count()),
+            });
+        }
+
+        let comment = self.open_comment.take().unwrap();
+
+        Ok(Some((comment.row, Line::comment(comment.text))))
+    }
+}
+
+/// A line is continued into the next physical line if it ends
+/// with a comma, or if it has an unclosed parenthesis.
+fn is_continued(content: &str) -> bool {
+    let content = content.trim_end();
+
+    if content.ends_with(',') && !content.ends_with("\\,") {
+        return true;
+    }
+
+    let mut parens = 0_isize;
+    let mut quote = None;
+    let mut chars = content.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            // escaped chars are never special
+            (_, '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(') => parens += 1,
+            (None, ')') => parens -= 1,
+            _ => (),
+        }
     }
+
+    parens > 0
+}
+
+/// A line whose value ends with a string, or that has no value
+/// yet (such as `grid-template-areas:`), may be continued by the
+/// physical lines starting with a string.
+fn accepts_string_continuation(content: &str) -> bool {
+    let is_escaped = ["\\\"", "\\'", "\\:"]
+        .iter()
+        .any(|escaped| content.ends_with(escaped));
+
+    content.ends_with(['"', '\'', ':']) && !is_escaped
+}
+
+fn is_continued_by_string(line: &Line, raw_line: &str) -> bool {
+    !line.is_comment()
+        && accepts_string_continuation(line.value())
+        && raw_line.trim_start().starts_with(['"', '\''])
 }
 
 #[derive(Default)]
@@ -120,7 +332,7 @@ impl IndentationHandler {
 
 #[cfg(test)]
 mod tests {
-    use super::Builder as LineBuilder;
+    use super::{Builder as LineBuilder, LineContent, Position};
     use indoc::indoc;
 
     #[test]
@@ -140,6 +352,198 @@ mod tests {
         }
     }
 
+    #[test]
+    fn build_lines_with_comments() {
+        let mut builder = LineBuilder::default();
+        let src = indoc! {"
+            // a line comment
+            foo // trailing
+              /* a block comment
+            that is not indented
+                */
+                bar: 0 /* inline */
+        "};
+
+        let lines = src
+            .lines()
+            .flat_map(|line| builder.build_line_from(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(builder.finish().is_ok());
+        assert_eq!(lines.len(), 4);
+
+        assert_eq!(lines[0].0, 2);
+        assert!(matches!(&lines[0].1.content, LineContent::Value(v) if v == "foo"));
+
+        assert_eq!(lines[1].0, 3);
+        assert!(matches!(
+            &lines[1].1.content,
+            LineContent::Comment(c) if c == "/* a block comment\nthat is not indented\n    */"
+        ));
+
+        // the inline comment is placed before the line of its code
+        assert_eq!(lines[2].0, 6);
+        assert!(matches!(&lines[2].1.content, LineContent::Comment(c) if c == "/* inline */"));
+        assert!(matches!(&lines[3].1.content, LineContent::Value(v) if v == "bar: 0"));
+    }
+
+    #[test]
+    fn build_lines_with_leading_comments() {
+        let mut builder = LineBuilder::default();
+        let src = indoc! {"
+            /* a */ .a
+                /* b */ color: red
+                margin: 0,
+                    /* c */
+                    1px
+        "};
+
+        let lines = src
+            .lines()
+            .flat_map(|line| builder.build_line_from(line).unwrap())
+            .map(|(row, line)| (row, line.value().to_string(), line.indentation.depth))
+            .collect::<Vec<_>>();
+
+        assert!(builder.finish().is_ok());
+
+        let expected = [
+            (1, "/* a */", 0),
+            (1, ".a", 0),
+            (2, "/* b */", 0),
+            (2, "color: red", 4),
+            (4, "/* c */", 0),
+            (3, "margin: 0, 1px", 4),
+        ];
+
+        let expected = expected
+            .iter()
+            .map(|(row, value, depth)| (*row, value.to_string(), *depth))
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn build_continued_lines() {
+        let mut builder = LineBuilder::default();
+        let src = indoc! {"
+            .a,
+              .b
+                box-shadow: 0 0 1px red,
+                        0 0 2px blue
+                transform: translate(
+                    10px,
+                  20px
+                )
+        "};
+
+        let lines = src
+            .lines()
+            .flat_map(|line| builder.build_line_from(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(builder.finish().is_ok());
+        assert_eq!(lines.len(), 3);
+
+        let values = lines
+            .iter()
+            .map(|(row, line)| (*row, line.value()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            values,
+            vec![
+                (1, ".a, .b"),
+                (3, "box-shadow: 0 0 1px red, 0 0 2px blue"),
+                (5, "transform: translate(10px, 20px)"),
+            ]
+        );
+
+        let continuation = &lines[1].1.continuations[0];
+        assert_eq!(continuation.offset, 25);
+        assert_eq!(continuation.pos, Position::at(4, 12));
+    }
+
+    #[test]
+    fn build_lines_with_escaped_chars() {
+        let mut builder = LineBuilder::default();
+
+        let lines = [".a\\,", ".b\\(", "    content: '('"]
+            .iter()
+            .flat_map(|line| builder.build_line_from(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 2);
+        assert!(builder.finish().unwrap().is_some());
+    }
+
+    #[test]
+    fn build_lines_continued_by_strings() {
+        let mut builder = LineBuilder::default();
+        let src = indoc! {r#"
+            .grid
+                grid-template-areas: "a b"
+                    "c d"
+                grid-template-areas:
+                    "a b"
+                    // comment
+                    "c d"
+                content: "a"
+                font:
+                    family: serif
+        "#};
+
+        let mut lines = src
+            .lines()
+            .flat_map(|line| builder.build_line_from(line).unwrap())
+            .collect::<Vec<_>>();
+
+        lines.extend(builder.finish().unwrap());
+
+        let values = lines
+            .iter()
+            .map(|(row, line)| (*row, line.value()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            values,
+            vec![
+                (1, ".grid"),
+                (2, r#"grid-template-areas: "a b" "c d""#),
+                (4, r#"grid-template-areas: "a b" "c d""#),
+                (8, r#"content: "a""#),
+                (9, "font:"),
+                (10, "family: serif"),
+            ]
+        );
+
+        let continuation = &lines[2].1.continuations[1];
+        assert_eq!(continuation.pos, Position::at(7, 8));
+    }
+
+    #[test]
+    fn build_with_unterminated_line() {
+        let mut builder = LineBuilder::default();
+
+        builder.build_line_from("foo,").unwrap();
+
+        assert!(builder.finish().is_err());
+    }
+
+    #[test]
+    fn build_with_unterminated_comment() {
+        let mut builder = LineBuilder::default();
+
+        builder.build_line_from("foo").unwrap();
+        builder.build_line_from("    /* unterminated").unwrap();
+
+        assert!(builder.finish().is_err());
+
+        let mut builder = LineBuilder::default();
+
+        assert!(builder.build_line_from("bar: 0 /* inline").is_err());
+    }
+
     #[test]
     fn build_with_inconsistent_indentation() {
         let mut builder = LineBuilder::default();
@@ -0,0 +1,136 @@
+use super::position::Col;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SplitLine {
+    pub code: String,
+    pub comment: String,
+    // the column of a block comment that is not closed until
+    // the end of the line
+    pub unclosed_at: Option<Col>,
+}
+
+/// Separate the code of a line from its comments.
+///
+/// `//` comments are dropped until the end of the line, unless
+/// they are inside a string or a parenthesis (for example in
+/// `url(http://...)`), or follow a non-whitespace char (for
+/// example in `--url: https://...`). The `/* */` comments are
+/// collected into `comment`.
+pub fn split(line: &str) -> SplitLine {
+    let mut split = SplitLine::default();
+    let mut quote = None;
+    let mut parens = 0_usize;
+    let mut chars = line.char_indices();
+    // the whitespaces after a leading block comment are skipped,
+    // so that the comment is not counted as an indentation
+    let mut skips_whitespaces = false;
+
+    while let Some((i, c)) = chars.next() {
+        if skips_whitespaces && c.is_whitespace() {
+            continue;
+        }
+
+        skips_whitespaces = false;
+
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            } else if c == '\\' {
+                split.code.push(c);
+                match chars.next() {
+                    Some((_, escaped)) => split.code.push(escaped),
+                    None => break,
+                }
+                continue;
+            }
+
+            split.code.push(c);
+            continue;
+        }
+
+        let rest = &line[i..];
+
+        if let Some(body) = rest.strip_prefix("/*") {
+            match body.find("*/") {
+                Some(end) => {
+                    let comment = &rest[..end + 4];
+
+                    if !split.comment.is_empty() {
+                        split.comment.push(' ');
+                    }
+
+                    split.comment.push_str(comment);
+                    skips_whitespaces = split.code.trim().is_empty();
+
+                    // skip the rest of the comment
+                    chars.nth(comment.chars().count() - 2);
+                }
+                None => {
+                    split.comment.push_str(rest);
+                    split.unclosed_at = Some(line[..i].chars().count());
+                    break;
+                }
+            }
+            continue;
+        }
+
+        let follows_whitespace = i == 0 || line[..i].ends_with(char::is_whitespace);
+
+        if rest.starts_with("//") && parens == 0 && follows_whitespace {
+            break;
+        }
+
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '(' => parens += 1,
+            ')' => parens = parens.saturating_sub(1),
+            _ => (),
+        }
+
+        split.code.push(c);
+    }
+
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_comments_from_code() {
+        let cases = [
+            ("color: red", "color: red", "", None),
+            ("color: red // note", "color: red ", "", None),
+            ("  // note", "  ", "", None),
+            (
+                "background: url(http://a.b/c.png)",
+                "background: url(http://a.b/c.png)",
+                "",
+                None,
+            ),
+            ("content: \"// /*\"", "content: \"// /*\"", "", None),
+            ("--url: https://x.y/z", "--url: https://x.y/z", "", None),
+            ("margin: 0 /* a */ auto", "margin: 0  auto", "/* a */", None),
+            ("  /* start", "  ", "/* start", Some(2)),
+            ("/*! license */", "", "/*! license */", None),
+            ("/* a */ .a", ".a", "/* a */", None),
+            (
+                "    /* a */ /* b */ color: red",
+                "    color: red",
+                "/* a */ /* b */",
+                None,
+            ),
+        ];
+
+        for (line, code, comment, unclosed_at) in cases {
+            let expected = SplitLine {
+                code: code.to_string(),
+                comment: comment.to_string(),
+                unclosed_at,
+            };
+
+            assert_eq!(split(line), expected, "failed at `{line}`");
+        }
+    }
+}
@@ -1,7 +1,9 @@
 pub type Row = usize;
 pub type Col = usize;
 
-#[derive(Debug)]
+/// Rows are starting from 1, while columns are starting from 0
+/// and counted in chars (not bytes).
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Position {
     pub row: Row,
     pub col: Col,
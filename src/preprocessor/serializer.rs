@@ -0,0 +1,779 @@
+use super::{
+    css::{AtRule, Declaration, Item, Rule, Stylesheet},
+    line::position::Position,
+};
+use crate::error::Warning;
+use itertools::Itertools;
+use serde::Deserialize;
+use std::str::FromStr;
+
+const INDENT: &str = "  ";
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    // every declaration on its own line
+    Expanded,
+    // like expanded, but the closing braces are placed at the end
+    // of the last line of the blocks
+    Nested,
+    // every rule on its own line
+    Compact,
+    // without any unnecessary whitespaces, semicolons and zeros
+    Compressed,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style::Expanded
+    }
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "expanded" => Ok(Style::Expanded),
+            "nested" => Ok(Style::Nested),
+            "compact" => Ok(Style::Compact),
+            "compressed" => Ok(Style::Compressed),
+            _ => Err(format!("invalid output style `{s}`")),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Serializer {
+    pub group_media_queries: bool,
+    pub style: Style,
+    // the src file to be referenced by the comments placed above
+    // each rule, which are omitted from the compressed output
+    pub line_comments: Option<String>,
+}
+
+/// The serialized stylesheet, along with the byte offsets of the
+/// emitted selectors and declarations that are paired with their
+/// positions in the src file, and the warnings of the compilation.
+#[derive(Debug, Default)]
+pub struct Output {
+    pub css: String,
+    pub mappings: Vec<(usize, Position)>,
+    // the warnings reported while compiling
+    pub warnings: Vec<Warning>,
+}
+
+impl Output {
+    fn map(&mut self, pos: Option<Position>) {
+        if let Some(pos) = pos {
+            self.mappings.push((self.css.len(), pos));
+        }
+    }
+}
+
+impl Serializer {
+    pub fn serialize(&self, stylesheet: Stylesheet) -> Output {
+        let mut items = stylesheet.items;
+
+        if self.group_media_queries {
+            items = group_media_queries(items);
+        }
+
+        let mut output = Output::default();
+
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 && matches!(self.style, Style::Expanded | Style::Nested) {
+                output.css.push('\n');
+            }
+
+            self.write_item(&mut output, item, 0);
+        }
+
+        if self.style == Style::Compressed && !output.css.is_empty() {
+            output.css.push('\n');
+        }
+
+        output
+    }
+
+    fn write_item(&self, output: &mut Output, item: &Item, depth: usize) {
+        match item {
+            Item::Rule(rule) => self.write_rule(output, rule, depth),
+            Item::AtRule(at_rule) => self.write_at_rule(output, at_rule, depth),
+            Item::Comment(text) => self.write_comment(output, text, depth),
+        }
+    }
+
+    fn write_rule(&self, output: &mut Output, rule: &Rule, depth: usize) {
+        let selectors = match self.style {
+            Style::Compressed => rule
+                .selectors
+                .iter()
+                .map(|selector| compress(selector, &[',', '>', '+', '~']))
+                .join(","),
+            _ => rule.selectors.join(", "),
+        };
+
+        if let (Some(file), Some(pos)) = (&self.line_comments, rule.pos) {
+            self.write_comment(output, &format!("/* {file}:{} */", pos.row), depth);
+        }
+
+        self.write_block(output, &selectors, rule.pos, &rule.declarations, &[], depth);
+    }
+
+    fn write_at_rule(&self, output: &mut Output, at_rule: &AtRule, depth: usize) {
+        let prelude = match self.style {
+            Style::Compressed => compress(&at_rule.prelude, &[',']).replace(": ", ":"),
+            _ => at_rule.prelude.clone(),
+        };
+
+        let head = match prelude.as_str() {
+            "" => format!("@{}", at_rule.name),
+            prelude => format!("@{} {prelude}", at_rule.name),
+        };
+
+        let statement = match &at_rule.items {
+            Some(items) => {
+                return self.write_block(output, &head, None, &at_rule.declarations, items, depth);
+            }
+            None if self.style == Style::Compressed => format!("{head};"),
+            None if self.style == Style::Compact => format!("{head};\n"),
+            None => format!("{}{head};\n", INDENT.repeat(depth)),
+        };
+
+        output.css.push_str(&statement);
+    }
+
+    fn write_comment(&self, output: &mut Output, text: &str, depth: usize) {
+        let comment = match self.style {
+            // only the comments that are marked as important (such
+            // as licenses) are kept in the compressed output
+            Style::Compressed if text.starts_with("/*!") => text.to_string(),
+            Style::Compressed => String::new(),
+            Style::Compact => format!("{text}\n"),
+            _ => format!("{}{text}\n", INDENT.repeat(depth)),
+        };
+
+        output.css.push_str(&comment);
+    }
+
+    fn write_block(
+        &self,
+        output: &mut Output,
+        head: &str,
+        pos: Option<Position>,
+        declarations: &[Declaration],
+        items: &[Item],
+        depth: usize,
+    ) {
+        match self.style {
+            Style::Expanded | Style::Nested => {
+                let indent = INDENT.repeat(depth);
+                let inner_indent = INDENT.repeat(depth + 1);
+
+                output.css.push_str(&indent);
+                output.map(pos);
+                output.css.push_str(&format!("{head} {{\n"));
+
+                for declaration in declarations {
+                    output.css.push_str(&inner_indent);
+                    self.write_declaration(output, declaration);
+                    output.css.push_str(";\n");
+                }
+
+                for item in items {
+                    self.write_item(output, item, depth + 1);
+                }
+
+                if self.style == Style::Nested {
+                    output.css.truncate(output.css.trim_end().len());
+                    output.css.push_str(" }\n");
+                } else {
+                    output.css.push_str(&format!("{indent}}}\n"));
+                }
+            }
+
+            Style::Compact => {
+                output.map(pos);
+                output.css.push_str(&format!("{head} {{"));
+
+                for declaration in declarations {
+                    output.css.push(' ');
+                    self.write_declaration(output, declaration);
+                    output.css.push(';');
+                }
+
+                for item in items {
+                    output.css.push(' ');
+                    self.write_item(output, item, depth + 1);
+                    output.css.truncate(output.css.trim_end().len());
+                }
+
+                output.css.push_str(" }\n");
+            }
+
+            Style::Compressed => {
+                output.map(pos);
+                output.css.push_str(&format!("{head}{{"));
+
+                for (i, declaration) in declarations.iter().enumerate() {
+                    if i > 0 {
+                        output.css.push(';');
+                    }
+
+                    self.write_declaration(output, declaration);
+                }
+
+                if !declarations.is_empty() {
+                    output.css.push(';');
+                }
+
+                let len = output.css.len();
+
+                for item in items {
+                    self.write_item(output, item, depth + 1);
+                }
+
+                // the last declaration needs a semicolon only if it
+                // is followed by nested blocks
+                if !declarations.is_empty() && output.css.len() == len {
+                    output.css.pop();
+                }
+
+                output.css.push('}');
+            }
+        }
+    }
+
+    fn write_declaration(&self, output: &mut Output, declaration: &Declaration) {
+        output.map(declaration.pos);
+        output
+            .css
+            .push_str(&self.serialize_declaration(declaration));
+    }
+
+    fn serialize_declaration(&self, declaration: &Declaration) -> String {
+        let property = &declaration.property;
+        let value = &declaration.value;
+
+        if self.style != Style::Compressed {
+            let important = if declaration.is_important {
+                " !important"
+            } else {
+                ""
+            };
+
+            return format!("{property}: {value}{important}");
+        }
+
+        let important = if declaration.is_important {
+            "!important"
+        } else {
+            ""
+        };
+
+        // the values of custom properties are kept verbatim
+        if property.starts_with("--") {
+            return format!("{property}:{value}{important}");
+        }
+
+        let value = remove_redundant_zeros(&compress(value, &[',', '/']));
+        format!("{property}:{value}{important}")
+    }
+}
+
+/// Merge all media blocks having identical queries into the
+/// position of the first one, while keeping the order of the
+/// rules inside them.
+fn group_media_queries(items: Vec<Item>) -> Vec<Item> {
+    let mut grouped: Vec<Item> = vec![];
+
+    for item in items {
+        let media = match item {
+            Item::AtRule(at_rule) if at_rule.name == "media" => at_rule,
+            item => {
+                grouped.push(item);
+                continue;
+            }
+        };
+
+        let existing = grouped.iter_mut().find_map(|item| match item {
+            Item::AtRule(at_rule)
+                if at_rule.name == "media" && at_rule.prelude == media.prelude =>
+            {
+                at_rule.items.as_mut()
+            }
+            _ => None,
+        });
+
+        match existing {
+            Some(existing) => existing.extend(media.items.unwrap_or_default()),
+            None => grouped.push(Item::AtRule(media)),
+        }
+    }
+
+    grouped
+}
+
+/// Collapse the whitespaces outside of strings into a single
+/// space, and remove them entirely around the given separators.
+fn compress(s: &str, separators: &[char]) -> String {
+    let mut output = String::new();
+    let mut quote = None;
+    let mut has_space = false;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            output.push(c);
+
+            if c == '\\' {
+                output.extend(chars.next());
+            } else if c == q {
+                quote = None;
+            }
+
+            continue;
+        }
+
+        if c.is_whitespace() {
+            has_space = true;
+            continue;
+        }
+
+        let is_separated = separators.contains(&c) || output.ends_with(separators);
+
+        if has_space && !output.is_empty() && !is_separated {
+            output.push(' ');
+        }
+
+        has_space = false;
+
+        match c {
+            '"' | '\'' => quote = Some(c),
+            // an escaped char (which may be a whitespace) is never
+            // collapsed
+            '\\' => {
+                output.push(c);
+                output.extend(chars.next());
+                continue;
+            }
+            _ => (),
+        }
+
+        output.push(c);
+    }
+
+    output
+}
+
+/// Remove the leading and trailing zeros of the numbers inside a
+/// value (e.g. `0.50px` into `.5px`). Strings and urls are kept
+/// as is.
+fn remove_redundant_zeros(value: &str) -> String {
+    let mut output = String::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut in_url = false;
+
+    for c in value.chars() {
+        if quote.is_some() || in_url {
+            output.push(c);
+
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (None, ')') => in_url = false,
+                _ => (),
+            }
+
+            continue;
+        }
+
+        if c.is_whitespace() || ",()/*\"'".contains(c) {
+            in_url = c == '(' && word.eq_ignore_ascii_case("url");
+            output.push_str(&trim_number_zeros(&word));
+            output.push(c);
+            word.clear();
+
+            if c == '"' || c == '\'' {
+                quote = Some(c);
+            }
+
+            continue;
+        }
+
+        word.push(c);
+    }
+
+    output.push_str(&trim_number_zeros(&word));
+    output
+}
+
+fn trim_number_zeros(word: &str) -> String {
+    let unsigned = word.trim_start_matches(['+', '-']);
+    let sign = &word[..word.len() - unsigned.len()];
+
+    let integer_len = unsigned.chars().take_while(char::is_ascii_digit).count();
+    let (integer, rest) = unsigned.split_at(integer_len);
+
+    let fraction = match rest.strip_prefix('.') {
+        Some(fraction) => fraction,
+        None => return word.to_string(),
+    };
+
+    let fraction_len = fraction.chars().take_while(char::is_ascii_digit).count();
+    let (fraction, suffix) = fraction.split_at(fraction_len);
+
+    // not a number (e.g. a class name)
+    if fraction.is_empty() || sign.len() > 1 {
+        return word.to_string();
+    }
+
+    let integer = integer.trim_start_matches('0');
+    let fraction = fraction.trim_end_matches('0');
+
+    let number = match (integer, fraction) {
+        ("", "") => String::from("0"),
+        (integer, "") => integer.to_string(),
+        (integer, fraction) => format!("{integer}.{fraction}"),
+    };
+
+    format!("{sign}{number}{suffix}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn serialize_stylesheet() {
+        let stylesheet = Stylesheet {
+            items: vec![
+                helpers::rule(".a", "color", "red"),
+                helpers::media("print", vec![helpers::rule(".b", "display", "none")]),
+            ],
+        };
+
+        let expected = indoc! {"
+            .a {
+              color: red;
+            }
+
+            @media print {
+              .b {
+                display: none;
+              }
+            }
+        "};
+
+        assert_eq!(Serializer::default().serialize(stylesheet).css, expected);
+    }
+
+    #[test]
+    fn serialize_important_declarations() {
+        let stylesheet = Stylesheet {
+            items: vec![Item::Rule(Rule {
+                selectors: vec![String::from(".a")],
+                declarations: vec![Declaration {
+                    property: String::from("color"),
+                    value: String::from("red"),
+                    is_important: true,
+                    pos: None,
+                }],
+                pos: None,
+            })],
+        };
+
+        let expected = indoc! {"
+            .a {
+              color: red !important;
+            }
+        "};
+
+        assert_eq!(Serializer::default().serialize(stylesheet).css, expected);
+    }
+
+    #[test]
+    fn serialize_at_rules() {
+        let stylesheet = Stylesheet {
+            items: vec![
+                Item::AtRule(AtRule {
+                    name: String::from("layer"),
+                    prelude: String::from("base, components"),
+                    declarations: vec![],
+                    items: None,
+                }),
+                Item::AtRule(AtRule {
+                    name: String::from("layer"),
+                    prelude: String::new(),
+                    declarations: vec![],
+                    items: Some(vec![helpers::rule(".a", "color", "red")]),
+                }),
+                Item::AtRule(AtRule {
+                    name: String::from("font-face"),
+                    prelude: String::new(),
+                    declarations: vec![Declaration {
+                        property: String::from("src"),
+                        value: String::from("url(a.woff)"),
+                        is_important: false,
+                        pos: None,
+                    }],
+                    items: Some(vec![]),
+                }),
+            ],
+        };
+
+        let expected = indoc! {"
+            @layer base, components;
+
+            @layer {
+              .a {
+                color: red;
+              }
+            }
+
+            @font-face {
+              src: url(a.woff);
+            }
+        "};
+
+        assert_eq!(Serializer::default().serialize(stylesheet).css, expected);
+    }
+
+    #[test]
+    fn serialize_with_grouped_media_queries() {
+        let stylesheet = Stylesheet {
+            items: vec![
+                helpers::media("print", vec![helpers::rule(".a", "color", "red")]),
+                helpers::rule(".b", "color", "blue"),
+                helpers::media("screen", vec![helpers::rule(".c", "color", "red")]),
+                helpers::media("print", vec![helpers::rule(".d", "color", "red")]),
+            ],
+        };
+
+        let expected = indoc! {"
+            @media print {
+              .a {
+                color: red;
+              }
+              .d {
+                color: red;
+              }
+            }
+
+            .b {
+              color: blue;
+            }
+
+            @media screen {
+              .c {
+                color: red;
+              }
+            }
+        "};
+
+        let serializer = Serializer {
+            group_media_queries: true,
+            ..Serializer::default()
+        };
+
+        assert_eq!(serializer.serialize(stylesheet).css, expected);
+    }
+
+    #[test]
+    fn serialize_with_styles() {
+        let stylesheet = || Stylesheet {
+            items: vec![
+                Item::Comment(String::from("/*! license */")),
+                Item::Rule(Rule {
+                    selectors: vec![String::from(".a > .b"), String::from(".c")],
+                    declarations: vec![
+                        Declaration {
+                            property: String::from("margin"),
+                            value: String::from("0.50em 1.0px, 0 auto"),
+                            is_important: false,
+                            pos: None,
+                        },
+                        Declaration {
+                            property: String::from("color"),
+                            value: String::from("red"),
+                            is_important: true,
+                            pos: None,
+                        },
+                    ],
+                    pos: None,
+                }),
+                helpers::media(
+                    "(min-width: 10px)",
+                    vec![helpers::rule(".d", "color", "red")],
+                ),
+            ],
+        };
+
+        let cases = [
+            (
+                Style::Nested,
+                indoc! {"
+                    /*! license */
+
+                    .a > .b, .c {
+                      margin: 0.50em 1.0px, 0 auto;
+                      color: red !important; }
+
+                    @media (min-width: 10px) {
+                      .d {
+                        color: red; } }
+                "},
+            ),
+            (
+                Style::Compact,
+                indoc! {"
+                    /*! license */
+                    .a > .b, .c { margin: 0.50em 1.0px, 0 auto; color: red !important; }
+                    @media (min-width: 10px) { .d { color: red; } }
+                "},
+            ),
+            (
+                Style::Compressed,
+                concat!(
+                    "/*! license */",
+                    ".a>.b,.c{margin:.5em 1px,0 auto;color:red!important}",
+                    "@media (min-width:10px){.d{color:red}}\n",
+                ),
+            ),
+        ];
+
+        for (style, expected) in cases {
+            let serializer = Serializer {
+                style,
+                ..Serializer::default()
+            };
+
+            assert_eq!(
+                serializer.serialize(stylesheet()).css,
+                expected,
+                "failed at `{style:?}`"
+            );
+        }
+    }
+
+    #[test]
+    fn serialize_with_mappings() {
+        let stylesheet = || Stylesheet {
+            items: vec![helpers::media(
+                "print",
+                vec![Item::Rule(Rule {
+                    selectors: vec![String::from(".a")],
+                    declarations: vec![Declaration {
+                        property: String::from("color"),
+                        value: String::from("red"),
+                        is_important: false,
+                        pos: Some(Position::at(3, 8)),
+                    }],
+                    pos: Some(Position::at(2, 4)),
+                })],
+            )],
+        };
+
+        let cases = [
+            (Style::Expanded, vec![17, 26]),
+            (Style::Compact, vec![15, 20]),
+            (Style::Compressed, vec![13, 16]),
+        ];
+
+        for (style, offsets) in cases {
+            let serializer = Serializer {
+                style,
+                ..Serializer::default()
+            };
+
+            let expected = offsets
+                .into_iter()
+                .zip([Position::at(2, 4), Position::at(3, 8)])
+                .collect::<Vec<_>>();
+
+            let output = serializer.serialize(stylesheet());
+            assert_eq!(output.mappings, expected, "failed at `{style:?}`");
+        }
+    }
+
+    #[test]
+    fn serialize_with_line_comments() {
+        let stylesheet = Stylesheet {
+            items: vec![helpers::media(
+                "print",
+                vec![Item::Rule(Rule {
+                    pos: Some(Position::at(12, 4)),
+                    ..helpers::rule_of(".a", "color", "red")
+                })],
+            )],
+        };
+
+        let expected = indoc! {"
+            @media print {
+              /* a.smly:12 */
+              .a {
+                color: red;
+              }
+            }
+        "};
+
+        let serializer = Serializer {
+            line_comments: Some(String::from("a.smly")),
+            ..Serializer::default()
+        };
+
+        assert_eq!(serializer.serialize(stylesheet).css, expected);
+    }
+
+    #[test]
+    fn compressed_values() {
+        let cases = [
+            ("1.50px", "1.5px"),
+            ("-0.5em +00.0", "-.5em +0"),
+            ("10.010 10", "10.01 10"),
+            ("url(0.50.png)", "url(0.50.png)"),
+            ("\"0.50\" 0.50", "\"0.50\" .5"),
+            ("a.0", "a.0"),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(
+                remove_redundant_zeros(value),
+                expected,
+                "failed at `{value}`"
+            );
+        }
+    }
+
+    mod helpers {
+        use super::*;
+
+        pub fn rule(selector: &str, property: &str, value: &str) -> Item {
+            Item::Rule(rule_of(selector, property, value))
+        }
+
+        pub fn rule_of(selector: &str, property: &str, value: &str) -> Rule {
+            Rule {
+                selectors: vec![selector.to_string()],
+                declarations: vec![Declaration {
+                    property: property.to_string(),
+                    value: value.to_string(),
+                    is_important: false,
+                    pos: None,
+                }],
+                pos: None,
+            }
+        }
+
+        pub fn media(query: &str, items: Vec<Item>) -> Item {
+            Item::AtRule(AtRule {
+                name: String::from("media"),
+                prelude: query.to_string(),
+                declarations: vec![],
+                items: Some(items),
+            })
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use super::line::position::Position;
+
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A source map (revision 3) of an out file that is compiled from
+/// a single src file.
+///
+/// Its positions are starting from 0 and counted in UTF-16 code
+/// units, as required by the specification.
+pub struct SourceMap {
+    // the name of the out file
+    file: String,
+    // the path of the src file, relative to the source map
+    source: String,
+    source_content: String,
+    // pairs of generated and original positions, ordered by the
+    // generated ones
+    segments: Vec<(Position, Position)>,
+}
+
+impl SourceMap {
+    /// Create a source map from the byte offsets inside the out
+    /// file content, paired with their positions in the src file.
+    pub fn new(
+        file: &str,
+        source: &str,
+        source_content: &str,
+        css: &str,
+        mappings: &[(usize, Position)],
+    ) -> Self {
+        let src_lines = source_content.lines().collect::<Vec<_>>();
+        let mut segments = vec![];
+
+        let mut generated = Position::at(0, 0);
+        let mut cursor = 0;
+
+        for (offset, original) in mappings {
+            for c in css[cursor..*offset].chars() {
+                if c == '\n' {
+                    generated = Position::at(generated.row + 1, 0);
+                } else {
+                    generated.col += c.len_utf16();
+                }
+            }
+
+            cursor = *offset;
+
+            let original_col = match src_lines.get(original.row - 1) {
+                Some(line) => line.chars().take(original.col).map(char::len_utf16).sum(),
+                None => original.col,
+            };
+
+            segments.push((generated, Position::at(original.row - 1, original_col)));
+        }
+
+        Self {
+            file: file.to_string(),
+            source: source.to_string(),
+            source_content: source_content.to_string(),
+            segments,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            r#"{{"version":3,"file":{},"sources":[{}],"sourcesContent":[{}],"names":[],"mappings":"{}"}}"#,
+            json_string(&self.file),
+            json_string(&self.source),
+            json_string(&self.source_content),
+            self.encode_mappings(),
+        )
+    }
+
+    /// Get the source map as a data URI, so that it can be
+    /// embedded into the out file.
+    pub fn to_data_uri(&self) -> String {
+        format!(
+            "data:application/json;charset=utf-8;base64,{}",
+            base64(self.to_json().as_bytes())
+        )
+    }
+
+    fn encode_mappings(&self) -> String {
+        let mut mappings = String::new();
+        let mut row = 0;
+        let mut previous_col = 0;
+        let mut previous_original = Position::at(0, 0);
+
+        for (i, (generated, original)) in self.segments.iter().enumerate() {
+            if generated.row > row {
+                mappings.push_str(&";".repeat(generated.row - row));
+                row = generated.row;
+                previous_col = 0;
+            } else if i > 0 {
+                mappings.push(',');
+            }
+
+            // only the generated columns are relative to the line,
+            // the rest are relative to the previous segment
+            let fields = [
+                delta(generated.col, previous_col),
+                0,
+                delta(original.row, previous_original.row),
+                delta(original.col, previous_original.col),
+            ];
+
+            for field in fields {
+                mappings.push_str(&vlq(field));
+            }
+
+            previous_col = generated.col;
+            previous_original = *original;
+        }
+
+        mappings
+    }
+}
+
+fn delta(value: usize, previous: usize) -> i64 {
+    value as i64 - previous as i64
+}
+
+/// Encode a number into a base64 VLQ, which stores the sign in
+/// the least significant bit, and 5 bits of the number per digit.
+fn vlq(value: i64) -> String {
+    let mut value = (value.unsigned_abs() << 1) | (value < 0) as u64;
+    let mut encoded = String::new();
+
+    loop {
+        let mut digit = value & 0b11111;
+        value >>= 5;
+
+        if value > 0 {
+            digit |= 0b100000;
+        }
+
+        encoded.push(BASE64_CHARS[digit as usize] as char);
+
+        if value == 0 {
+            return encoded;
+        }
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |n, (i, byte)| n | (*byte as usize) << (16 - i * 8));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_CHARS[(n >> (18 - i * 6)) & 0b111111] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::from('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn vlq_encoding() {
+        let cases = [
+            (0, "A"),
+            (1, "C"),
+            (-1, "D"),
+            (15, "e"),
+            (16, "gB"),
+            (-100, "pG"),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(vlq(value), expected, "failed at `{value}`");
+        }
+    }
+
+    #[test]
+    fn base64_encoding() {
+        let cases = [("", ""), ("a", "YQ=="), ("ab", "YWI="), ("abc", "YWJj")];
+
+        for (value, expected) in cases {
+            assert_eq!(base64(value.as_bytes()), expected, "failed at `{value}`");
+        }
+    }
+
+    #[test]
+    fn source_map_generation() {
+        let src = indoc! {"
+            .a
+                color: red
+                .b
+                    margin: 0
+        "};
+
+        let css = indoc! {"
+            .a {
+              color: red;
+            }
+
+            .a .b {
+              margin: 0;
+            }
+        "};
+
+        let mappings = [
+            (0, Position::at(1, 0)),
+            (7, Position::at(2, 4)),
+            (22, Position::at(3, 4)),
+            (32, Position::at(4, 8)),
+        ];
+
+        let source_map = SourceMap::new("a.css", "a.smly", src, css, &mappings);
+
+        assert_eq!(
+            source_map.to_json(),
+            concat!(
+                r#"{"version":3,"file":"a.css","sources":["a.smly"],"#,
+                r#""sourcesContent":[".a\n    color: red\n    .b\n        margin: 0\n"],"#,
+                r#""names":[],"mappings":"AAAA;EACI;;;AACA;EACI""#,
+                "}",
+            )
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use super::line::{
+    self,
+    position::{Position, Row},
+    Content as LineContent, Line, LineKind, NumberedLine,
+};
+
+#[derive(Debug)]
+pub struct Node {
+    pub row: Row,
+    pub line: Line,
+    pub kind: LineKind,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn content(&self) -> &str {
+        match &self.line.content {
+            LineContent::Value(value) | LineContent::Comment(value) => value,
+            LineContent::Eof => unreachable!("eof line should not be part of the tree"),
+        }
+    }
+
+    pub fn is_comment(&self) -> bool {
+        self.line.is_comment()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.line.indentation.depth
+    }
+
+    /// Get the physical position of a char offset inside the
+    /// content, which may be located in a continuation line.
+    pub fn position_at(&self, offset: usize) -> Position {
+        let continuation = self
+            .line
+            .continuations
+            .iter()
+            .rev()
+            .find(|continuation| continuation.offset <= offset);
+
+        match continuation {
+            Some(continuation) => Position::at(
+                continuation.pos.row,
+                continuation.pos.col + offset - continuation.offset,
+            ),
+            None => Position::at(self.row, self.depth() + offset),
+        }
+    }
+}
+
+/// Arrange the lines into trees based on their indentation
+/// depths.
+///
+/// The lines are expected to have been validated by the line
+/// builder (so the indentation levels are consistent), and to
+/// be terminated by an eof line.
+///
+/// Comments are placed at the same depth as the line following
+/// them, and are skipped when determining the kind of the other
+/// lines.
+pub fn build(lines: &[NumberedLine]) -> Vec<Node> {
+    let mut roots = vec![];
+    let mut stack: Vec<Node> = vec![];
+
+    // the last line is always the eof line
+    for (i, (row, line)) in lines[..lines.len() - 1].iter().enumerate() {
+        let (_, next) = lines[i + 1..]
+            .iter()
+            .find(|(_, next)| !next.is_comment())
+            .unwrap();
+
+        let mut line = line.clone();
+
+        let kind = if line.is_comment() {
+            line.indentation = next.indentation;
+            LineKind::Declaration
+        } else {
+            line::determine_kind(&line, next)
+        };
+
+        while stack
+            .last()
+            .map_or(false, |open| open.depth() >= line.indentation.depth)
+        {
+            let node = stack.pop().unwrap();
+            attach(node, &mut stack, &mut roots);
+        }
+
+        stack.push(Node {
+            row: *row,
+            line,
+            kind,
+            children: vec![],
+        });
+    }
+
+    while let Some(node) = stack.pop() {
+        attach(node, &mut stack, &mut roots);
+    }
+
+    roots
+}
+
+fn attach(node: Node, stack: &mut [Node], roots: &mut Vec<Node>) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessor::line::builder::Builder as LineBuilder;
+    use indoc::indoc;
+
+    #[test]
+    fn build_nested_tree() {
+        let src = indoc! {"
+            a
+                b
+                    c
+
+                    d
+                e
+            f
+        "};
+
+        let mut builder = LineBuilder::default();
+        let mut lines = src
+            .lines()
+            .flat_map(|l| builder.build_line_from(l).unwrap())
+            .collect::<Vec<_>>();
+        lines.push((0, Line::eof()));
+
+        let tree = build(&lines);
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].content(), "a");
+        assert_eq!(tree[0].kind, LineKind::Selector);
+        assert_eq!(tree[0].children.len(), 2);
+        assert_eq!(tree[0].children[0].children.len(), 2);
+        assert_eq!(tree[0].children[0].children[1].content(), "d");
+        assert_eq!(tree[0].children[0].children[1].row, 5);
+        assert_eq!(tree[0].children[1].kind, LineKind::Declaration);
+        assert_eq!(tree[1].content(), "f");
+        assert!(tree[1].children.is_empty());
+    }
+
+    #[test]
+    fn build_tree_with_comments() {
+        let src = indoc! {"
+            a
+            /* x */
+                b
+                /* y */
+            c
+        "};
+
+        let mut builder = LineBuilder::default();
+        let mut lines = src
+            .lines()
+            .flat_map(|l| builder.build_line_from(l).unwrap())
+            .collect::<Vec<_>>();
+        lines.push((0, Line::eof()));
+
+        let tree = build(&lines);
+
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree[0].kind, LineKind::Selector);
+        assert_eq!(tree[0].children.len(), 2);
+        assert!(tree[0].children[0].is_comment());
+        assert!(tree[1].is_comment());
+        assert_eq!(tree[2].content(), "c");
+    }
+}
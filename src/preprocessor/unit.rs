@@ -0,0 +1,178 @@
+use std::f64::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Category {
+    Length,
+    Angle,
+    Time,
+    Frequency,
+    Resolution,
+    Flex,
+}
+
+/// The units known by the preprocessor, grouped by the kind of
+/// quantity they are measuring. Units that are not in this table
+/// are still accepted, but are reported as unknown.
+///
+/// Absolute units also have their size relative to the canonical
+/// unit of their category (`px`, `deg`, `s`, `hz` and `dppx`), so
+/// that they can be converted into each other.
+pub const UNITS: &[(&str, Category, Option<f64>)] = &[
+    // absolute lengths
+    ("px", Category::Length, Some(1.0)),
+    ("cm", Category::Length, Some(96.0 / 2.54)),
+    ("mm", Category::Length, Some(96.0 / 25.4)),
+    ("q", Category::Length, Some(96.0 / 101.6)),
+    ("in", Category::Length, Some(96.0)),
+    ("pt", Category::Length, Some(96.0 / 72.0)),
+    ("pc", Category::Length, Some(16.0)),
+    // font-relative lengths
+    ("em", Category::Length, None),
+    ("rem", Category::Length, None),
+    ("ex", Category::Length, None),
+    ("rex", Category::Length, None),
+    ("cap", Category::Length, None),
+    ("rcap", Category::Length, None),
+    ("ch", Category::Length, None),
+    ("rch", Category::Length, None),
+    ("ic", Category::Length, None),
+    ("ric", Category::Length, None),
+    ("lh", Category::Length, None),
+    ("rlh", Category::Length, None),
+    // viewport-relative lengths
+    ("vw", Category::Length, None),
+    ("vh", Category::Length, None),
+    ("vi", Category::Length, None),
+    ("vb", Category::Length, None),
+    ("vmin", Category::Length, None),
+    ("vmax", Category::Length, None),
+    ("svw", Category::Length, None),
+    ("svh", Category::Length, None),
+    ("svi", Category::Length, None),
+    ("svb", Category::Length, None),
+    ("svmin", Category::Length, None),
+    ("svmax", Category::Length, None),
+    ("lvw", Category::Length, None),
+    ("lvh", Category::Length, None),
+    ("lvi", Category::Length, None),
+    ("lvb", Category::Length, None),
+    ("lvmin", Category::Length, None),
+    ("lvmax", Category::Length, None),
+    ("dvw", Category::Length, None),
+    ("dvh", Category::Length, None),
+    ("dvi", Category::Length, None),
+    ("dvb", Category::Length, None),
+    ("dvmin", Category::Length, None),
+    ("dvmax", Category::Length, None),
+    // container-relative lengths
+    ("cqw", Category::Length, None),
+    ("cqh", Category::Length, None),
+    ("cqi", Category::Length, None),
+    ("cqb", Category::Length, None),
+    ("cqmin", Category::Length, None),
+    ("cqmax", Category::Length, None),
+    // angles
+    ("deg", Category::Angle, Some(1.0)),
+    ("grad", Category::Angle, Some(0.9)),
+    ("rad", Category::Angle, Some(180.0 / PI)),
+    ("turn", Category::Angle, Some(360.0)),
+    // times
+    ("s", Category::Time, Some(1.0)),
+    ("ms", Category::Time, Some(0.001)),
+    // frequencies
+    ("hz", Category::Frequency, Some(1.0)),
+    ("khz", Category::Frequency, Some(1000.0)),
+    // resolutions
+    ("dppx", Category::Resolution, Some(1.0)),
+    ("x", Category::Resolution, Some(1.0)),
+    ("dpi", Category::Resolution, Some(1.0 / 96.0)),
+    ("dpcm", Category::Resolution, Some(2.54 / 96.0)),
+    // flexible lengths
+    ("fr", Category::Flex, None),
+];
+
+/// Get the category of a unit, whose name is case-insensitive.
+pub fn category_of(name: &str) -> Option<Category> {
+    UNITS
+        .iter()
+        .find(|(unit, ..)| unit.eq_ignore_ascii_case(name))
+        .map(|(_, category, _)| *category)
+}
+
+/// Get the factor to multiply a value in the `from` unit with,
+/// so that it is expressed in the `to` unit. Returns `None` if
+/// the units are not compatible.
+pub fn conversion_factor(from: &str, to: &str) -> Option<f64> {
+    if from.eq_ignore_ascii_case(to) {
+        return Some(1.0);
+    }
+
+    let size_of = |name: &str| {
+        UNITS
+            .iter()
+            .find(|(unit, ..)| unit.eq_ignore_ascii_case(name))
+            .and_then(|(_, category, size)| Some((*category, (*size)?)))
+    };
+
+    match (size_of(from)?, size_of(to)?) {
+        ((from_category, from_size), (to_category, to_size)) if from_category == to_category => {
+            Some(from_size / to_size)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_categorization() {
+        let cases = [
+            ("px", Some(Category::Length)),
+            ("PX", Some(Category::Length)),
+            ("dvh", Some(Category::Length)),
+            ("cqi", Some(Category::Length)),
+            ("rad", Some(Category::Angle)),
+            ("ms", Some(Category::Time)),
+            ("kHz", Some(Category::Frequency)),
+            ("x", Some(Category::Resolution)),
+            ("fr", Some(Category::Flex)),
+            ("foo", None),
+        ];
+
+        for (unit, expected) in cases {
+            assert_eq!(category_of(unit), expected, "failed at `{unit}`");
+        }
+    }
+
+    #[test]
+    fn unit_conversion() {
+        let cases = [
+            ("in", "px", Some(96.0)),
+            ("px", "in", Some(1.0 / 96.0)),
+            ("s", "ms", Some(1000.0)),
+            ("turn", "deg", Some(360.0)),
+            ("em", "em", Some(1.0)),
+            ("foo", "FOO", Some(1.0)),
+            ("em", "px", None),
+            ("px", "deg", None),
+            ("fr", "px", None),
+            ("foo", "px", None),
+        ];
+
+        for (from, to, expected) in cases {
+            let factor = conversion_factor(from, to);
+
+            match (factor, expected) {
+                (Some(factor), Some(expected)) => {
+                    assert!(
+                        (factor - expected).abs() < 1e-9,
+                        "failed at `{from}` to `{to}`"
+                    )
+                }
+                _ => assert_eq!(factor, expected, "failed at `{from}` to `{to}`"),
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+/// The variables visible from the block being compiled. Each
+/// block has its own scope, and the first scope is the root one.
+#[derive(Default)]
+pub struct Variables {
+    scopes: Vec<HashMap<String, String>>,
+}
+
+impl Variables {
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Get the value of a variable from the innermost scope that
+    /// declares it.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .map(String::as_str)
+    }
+
+    /// Assign a variable into the innermost scope, or into the
+    /// root scope if it is global.
+    pub fn set(&mut self, name: &str, value: String, is_global: bool) {
+        let scope = if is_global {
+            self.scopes.first_mut()
+        } else {
+            self.scopes.last_mut()
+        };
+
+        scope
+            .expect("there should be at least one scope")
+            .insert(name.to_string(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoped_variables() {
+        let mut variables = Variables::default();
+
+        variables.push_scope();
+        variables.set("a", String::from("1px"), false);
+
+        variables.push_scope();
+        variables.set("a", String::from("2px"), false);
+        variables.set("b", String::from("red"), true);
+        assert_eq!(variables.get("a"), Some("2px"));
+
+        variables.pop_scope();
+        assert_eq!(variables.get("a"), Some("1px"));
+        assert_eq!(variables.get("b"), Some("red"));
+        assert_eq!(variables.get("c"), None);
+    }
+}
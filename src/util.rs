@@ -1,32 +1,39 @@
-use log::error;
 use std::{
+    env, io,
     path::{Path, PathBuf},
-    process,
 };
 
-pub fn assert_file_exists(file: &Path) {
-    if !file.exists() {
-        error!("File does not exist: `{}`", file.display());
-        process::exit(1);
-    }
+/// Check whether the path is `-`, which stands for either stdin
+/// or stdout.
+pub fn is_std_stream(file: &Path) -> bool {
+    file == Path::new("-")
 }
 
-pub fn assert_src_file_extension(file: &Path) {
-    let extension = file.extension();
+/// Get the default out file of a src file, which is placed next
+/// to it.
+pub fn create_default_out_file_pathbuf(file: &Path) -> PathBuf {
+    file.with_extension("css")
+}
 
-    if let Some(ext) = extension {
-        if ext == "smly" {
-            return;
-        }
-    }
+/// Get the url of a path relative to the `base` directory. The
+/// paths are only compared by their components, without resolving
+/// any symlinks.
+pub fn relative_url(path: &Path, base: &Path) -> io::Result<String> {
+    let cwd = env::current_dir()?;
+    let path = cwd.join(path);
+    let base = cwd.join(base);
 
-    error!("Invalid extension: Smiley src files should have `.smly` extension");
-    process::exit(1);
-}
+    let path = path.components().collect::<Vec<_>>();
+    let base = base.components().collect::<Vec<_>>();
 
-pub fn create_default_out_file_pathbuf(file: &Path) -> PathBuf {
-    let file = file.with_extension("css");
-    Path::new(file.file_name().unwrap()).to_path_buf()
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+
+    let parents = (common..base.len()).map(|_| String::from(".."));
+    let rest = path[common..]
+        .iter()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned());
+
+    Ok(parents.chain(rest).collect::<Vec<_>>().join("/"))
 }
 
 #[cfg(test)]
@@ -38,6 +45,22 @@ mod tests {
         let src = Path::new("path/to/src.smly");
         let pathbuf = create_default_out_file_pathbuf(&src);
 
-        assert_eq!(pathbuf.as_os_str(), "src.css");
+        assert_eq!(pathbuf.as_os_str(), "path/to/src.css");
+    }
+
+    #[test]
+    fn relative_url_creation() {
+        let cases = [
+            ("a.smly", "", "a.smly"),
+            ("src/a.smly", "", "src/a.smly"),
+            ("src/a.smly", "out", "../src/a.smly"),
+            ("src/a.smly", "src", "a.smly"),
+            ("/src/a.smly", "/out/css", "../../src/a.smly"),
+        ];
+
+        for (path, base, expected) in cases {
+            let url = relative_url(Path::new(path), Path::new(base)).unwrap();
+            assert_eq!(url, expected, "failed at `{path}` from `{base}`");
+        }
     }
 }
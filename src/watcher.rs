@@ -0,0 +1,122 @@
+use crate::importer::{FsImporter, Importer};
+use log::debug;
+use std::{
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// the files should stay unchanged for this long before a change is
+// reported, so that bursts of writes (such as the editors replacing
+// the files on save) only trigger a single build
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(100);
+
+// the modification time of each watched path, or `None` if it does
+// not exist
+type Snapshot = Vec<Option<SystemTime>>;
+
+/// Watch a set of files by polling their modification times, along
+/// with the directories in which files may be added or removed.
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    snapshot: Snapshot,
+}
+
+impl Watcher {
+    pub fn new(files: &[PathBuf], dirs: &[PathBuf]) -> Self {
+        let paths = files.iter().chain(dirs).cloned().collect::<Vec<_>>();
+        let snapshot = take_snapshot(&paths);
+
+        Self { paths, snapshot }
+    }
+
+    /// Block until any of the paths has been changed, created or
+    /// removed.
+    pub fn wait_for_change(&mut self) {
+        let mut snapshot = loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let snapshot = take_snapshot(&self.paths);
+
+            if snapshot != self.snapshot {
+                break snapshot;
+            }
+        };
+
+        debug!("Change detected, waiting for the writes to settle");
+
+        loop {
+            thread::sleep(DEBOUNCE_DELAY);
+
+            let next = take_snapshot(&self.paths);
+
+            if next == snapshot {
+                break;
+            }
+
+            snapshot = next;
+        }
+
+        self.snapshot = snapshot;
+    }
+}
+
+fn take_snapshot(paths: &[PathBuf]) -> Snapshot {
+    paths
+        .iter()
+        .map(|path| FsImporter.modified(path).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::{
+        fixture::{FileWriteStr, PathChild},
+        NamedTempFile, TempDir,
+    };
+    use std::sync::mpsc;
+
+    #[test]
+    fn watch_file_changes() {
+        let file = NamedTempFile::new("watched.smly").unwrap();
+        file.write_str("").unwrap();
+
+        let mut watcher = Watcher::new(&[file.path().to_path_buf()], &[]);
+        let (sender, receiver) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            watcher.wait_for_change();
+            sender.send(()).unwrap();
+        });
+
+        assert!(receiver.recv_timeout(Duration::from_millis(500)).is_err());
+
+        file.write_str(".a\n    color: red\n").unwrap();
+
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn watch_added_files() {
+        let dir = TempDir::new().unwrap();
+
+        let mut watcher = Watcher::new(&[], &[dir.path().to_path_buf()]);
+        let (sender, receiver) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            watcher.wait_for_change();
+            sender.send(()).unwrap();
+        });
+
+        assert!(receiver.recv_timeout(Duration::from_millis(500)).is_err());
+
+        dir.child("added.smly").write_str("").unwrap();
+
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+        handle.join().unwrap();
+    }
+}
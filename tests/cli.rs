@@ -1,18 +1,31 @@
 use assert_cmd::prelude::*;
-use assert_fs::fixture::{FileWriteStr, NamedTempFile};
-use indoc::indoc;
+use assert_fs::{
+    fixture::{FileWriteBin, FileWriteStr, NamedTempFile, PathChild},
+    TempDir,
+};
+use indoc::{formatdoc, indoc};
 use predicates::prelude::*;
-use std::process::Command;
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
 
 #[test]
 fn run_with_src_file() {
     let file = NamedTempFile::new("srcfile.smly").unwrap();
     file.write_str("").unwrap();
 
+    let dir = TempDir::new().unwrap();
+
     let mut cmd = Command::cargo_bin("smiley").unwrap();
-    cmd.arg(file.path());
+    cmd.current_dir(dir.path()).arg(file.path());
 
     cmd.assert().success();
+    assert!(file.path().with_extension("css").exists());
+    assert!(!dir.child("srcfile.css").exists());
 }
 
 #[test]
@@ -50,24 +63,41 @@ fn run_with_invalid_src_file_extension() {
         .stderr(predicate::str::contains("Invalid extension"));
 }
 
+#[test]
+fn run_with_unwritable_out_file() {
+    let file = NamedTempFile::new("unwritable.smly").unwrap();
+    file.write_str(".a\n    color: red\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(file.path().join("out.css"));
+
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("Unable to write file"));
+}
+
 #[test]
 fn run_with_expected_indentations() {
     let file = NamedTempFile::new("indentation.smly").unwrap();
     file.write_str(indoc! {"
         abc
             def
-                ghi
-                jkl
-            mno
+                ghi: 0
+                jkl: 0
+            mno: 0
         pqr
-                stu
+                stu: 0
                 vwx
-                        yz
+                        yz: 0
     "})
         .unwrap();
 
+    let dir = TempDir::new().unwrap();
+
     let mut cmd = Command::cargo_bin("smiley").unwrap();
-    cmd.arg(file.path());
+    cmd.current_dir(dir.path()).arg(file.path());
 
     cmd.assert().success();
 }
@@ -118,3 +148,693 @@ fn run_with_unexpected_indentations() {
             .stderr(predicate::str::contains("Unexpected indentation"));
     }
 }
+
+#[test]
+fn run_with_nested_rules() {
+    let file = NamedTempFile::new("nested.smly").unwrap();
+    file.write_str(indoc! {"
+        .card
+            color: red
+            .title
+                font-size: 1.5em
+            @media print
+                display: none
+    "})
+        .unwrap();
+
+    let out = NamedTempFile::new("nested.css").unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(file.path()).arg("-o").arg(out.path());
+
+    cmd.assert().success();
+
+    let expected = indoc! {"
+        .card {
+          color: red;
+        }
+
+        .card .title {
+          font-size: 1.5em;
+        }
+
+        @media print {
+          .card {
+            display: none;
+          }
+        }
+    "};
+
+    assert_eq!(fs::read_to_string(out.path()).unwrap(), expected);
+}
+
+#[test]
+fn run_with_grouped_media_queries() {
+    let file = NamedTempFile::new("grouped.smly").unwrap();
+    file.write_str(indoc! {"
+        .a
+            @media print
+                color: red
+        .b
+            color: blue
+            @media print
+                color: green
+    "})
+        .unwrap();
+
+    let out = NamedTempFile::new("grouped.css").unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(out.path())
+        .arg("--group-media-queries");
+
+    cmd.assert().success();
+
+    let expected = indoc! {"
+        @media print {
+          .a {
+            color: red;
+          }
+          .b {
+            color: green;
+          }
+        }
+
+        .b {
+          color: blue;
+        }
+    "};
+
+    assert_eq!(fs::read_to_string(out.path()).unwrap(), expected);
+}
+
+#[test]
+fn run_with_compressed_style() {
+    let file = NamedTempFile::new("compressed.smly").unwrap();
+    file.write_str(indoc! {"
+        .a
+            margin: 0.50em auto
+            .b
+                color: red
+    "})
+        .unwrap();
+
+    let out = NamedTempFile::new("compressed.css").unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(out.path())
+        .arg("--style")
+        .arg("compressed");
+
+    cmd.assert().success();
+
+    let expected = ".a{margin:.5em auto}.a .b{color:red}\n";
+
+    assert_eq!(fs::read_to_string(out.path()).unwrap(), expected);
+}
+
+#[test]
+fn run_with_source_map() {
+    let file = NamedTempFile::new("mapped.smly").unwrap();
+    file.write_str(indoc! {"
+        .a
+            color: red
+    "})
+        .unwrap();
+
+    let out = NamedTempFile::new("mapped.css").unwrap();
+    let map = out.path().with_file_name("mapped.css.map");
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(out.path())
+        .arg("--source-map");
+
+    cmd.assert().success();
+
+    let expected = indoc! {"
+        .a {
+          color: red;
+        }
+
+        /*# sourceMappingURL=mapped.css.map */
+    "};
+
+    assert_eq!(fs::read_to_string(out.path()).unwrap(), expected);
+
+    let source_map = fs::read_to_string(&map).unwrap();
+    assert!(source_map.contains(r#""file":"mapped.css""#));
+    assert!(source_map.contains(r#""mappings":"AAAA;EACI""#));
+
+    fs::remove_file(map).unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(out.path())
+        .arg("--embed-source-map");
+
+    cmd.assert().success();
+
+    let css = fs::read_to_string(out.path()).unwrap();
+    assert!(css.contains("/*# sourceMappingURL=data:application/json;charset=utf-8;base64,"));
+}
+
+#[test]
+fn run_with_line_comments() {
+    let file = NamedTempFile::new("commented.smly").unwrap();
+    file.write_str(indoc! {"
+        .a
+            color: red
+            .b
+                color: blue
+    "})
+        .unwrap();
+
+    let out = NamedTempFile::new("commented.css").unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(out.path())
+        .arg("--line-comments");
+
+    cmd.assert().success();
+
+    let file = file.path().display();
+    let expected = formatdoc! {"
+        /* {file}:1 */
+        .a {{
+          color: red;
+        }}
+
+        /* {file}:3 */
+        .a .b {{
+          color: blue;
+        }}
+    "};
+
+    assert_eq!(fs::read_to_string(out.path()).unwrap(), expected);
+}
+
+#[test]
+fn run_with_stdin_and_stdout() {
+    // only the `Command` of assert_cmd is able to write to stdin
+    let mut cmd = assert_cmd::Command::cargo_bin("smiley").unwrap();
+    cmd.arg("-").write_stdin(indoc! {"
+        .a
+            color: red
+    "});
+
+    cmd.assert().success().stdout(indoc! {"
+        .a {
+          color: red;
+        }
+    "});
+
+    let file = NamedTempFile::new("stdout.smly").unwrap();
+    file.write_str(indoc! {"
+        .b
+            color: blue
+    "})
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(file.path()).arg("-o").arg("-");
+
+    cmd.assert().success().stdout(indoc! {"
+        .b {
+          color: blue;
+        }
+    "});
+}
+
+#[test]
+fn run_with_invalid_stdin() {
+    // only the `Command` of assert_cmd is able to write to stdin
+    let mut cmd = assert_cmd::Command::cargo_bin("smiley").unwrap();
+    cmd.arg("-")
+        .arg("--stdin-filename")
+        .arg("piped.smly")
+        .write_stdin(indoc! {"
+            .a
+                color red
+        "});
+
+    cmd.assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("piped.smly:2:4"))
+        .stderr(predicate::str::contains("`    color red`"));
+}
+
+#[test]
+fn run_with_non_utf8_stdin() {
+    // only the `Command` of assert_cmd is able to write to stdin
+    let mut cmd = assert_cmd::Command::cargo_bin("smiley").unwrap();
+    cmd.arg("-").write_stdin(vec![b'.', b'a', 0xff, 0xfe]);
+
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("Unable to read file"))
+        .stderr(predicate::str::contains("stdin:1:0"));
+}
+
+#[test]
+fn run_with_watch_mode() {
+    let file = NamedTempFile::new("watched.smly").unwrap();
+    file.write_str(indoc! {"
+        .a
+            color red
+    "})
+        .unwrap();
+
+    let out = NamedTempFile::new("watched.css").unwrap();
+
+    // the watcher should keep running after the first compilation
+    // has failed
+    let mut child = Command::cargo_bin("smiley")
+        .unwrap()
+        .arg(file.path())
+        .arg("-o")
+        .arg(out.path())
+        .arg("--watch")
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    thread::sleep(Duration::from_millis(500));
+    assert!(!out.path().exists());
+
+    file.write_str(indoc! {"
+        .a
+            color: red
+    "})
+        .unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+
+    while !out.path().exists() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let expected = indoc! {"
+        .a {
+          color: red;
+        }
+    "};
+
+    assert_eq!(fs::read_to_string(out.path()).unwrap(), expected);
+}
+
+#[test]
+fn run_with_watch_mode_on_src_dir() {
+    let src = TempDir::new().unwrap();
+    src.child("main.smly")
+        .write_str(".a\n    color: red\n")
+        .unwrap();
+    src.child("removed.smly")
+        .write_str(".b\n    color: red\n")
+        .unwrap();
+
+    let mut child = Command::cargo_bin("smiley")
+        .unwrap()
+        .arg(src.path())
+        .arg("--watch")
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let wait_for = |file: &Path| {
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        while !file.exists() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        file.exists()
+    };
+
+    assert!(wait_for(src.child("main.css").path()));
+
+    // the watcher should keep running after a src file is removed,
+    // and pick up the ones added into the directory
+    fs::remove_file(src.child("removed.smly").path()).unwrap();
+    thread::sleep(Duration::from_millis(500));
+
+    src.child("pages/added.smly")
+        .write_str(".c\n    color: red\n")
+        .unwrap();
+
+    let is_added = wait_for(src.child("pages/added.css").path());
+    let is_running = child.try_wait().unwrap().is_none();
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert!(is_added);
+    assert!(is_running);
+}
+
+#[test]
+fn run_with_src_dir() {
+    let src = TempDir::new().unwrap();
+    src.child("main.smly")
+        .write_str(".a\n    color: red\n")
+        .unwrap();
+    src.child("_partial.smly")
+        .write_str(".b\n    color: red\n")
+        .unwrap();
+    src.child("pages/home.smly")
+        .write_str(".c\n    color: red\n")
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(src.path());
+
+    cmd.assert().success();
+
+    assert!(src.child("main.css").exists());
+    assert!(src.child("pages/home.css").exists());
+    assert!(!src.child("_partial.css").exists());
+
+    let out = TempDir::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(format!("{}:{}", src.path().display(), out.path().display()));
+
+    cmd.assert().success();
+
+    let expected = indoc! {"
+        .c {
+          color: red;
+        }
+    "};
+
+    assert!(out.child("main.css").exists());
+    assert!(!out.child("_partial.css").exists());
+    assert_eq!(
+        fs::read_to_string(out.child("pages/home.css").path()).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn run_with_multiple_src_files() {
+    let src = TempDir::new().unwrap();
+    src.child("main.smly")
+        .write_str(".a\n    color: red\n")
+        .unwrap();
+    src.child("pages/home.smly")
+        .write_str(".b\n    color red\n")
+        .unwrap();
+    src.child("pages/blog.smly")
+        .write_str(".c\n    color red\n")
+        .unwrap();
+
+    let out = TempDir::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(src.child("main.smly").path())
+        .arg(src.path().join("pages/*.smly"))
+        .arg("-o")
+        .arg(out.path());
+
+    // every failure should be reported, without stopping the
+    // compilation of the other files
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("home.smly:2:4"))
+        .stderr(predicate::str::contains("blog.smly:2:4"));
+
+    assert!(out.child("main.css").exists());
+    assert!(!out.child("home.css").exists());
+}
+
+#[test]
+fn run_with_glob_patterns() {
+    let src = TempDir::new().unwrap();
+
+    for file in [
+        "main.smly",
+        "_partial.smly",
+        "pages/home.smly",
+        "blog/home.smly",
+    ] {
+        src.child(file).write_str(".a\n    color: red\n").unwrap();
+    }
+
+    let out = TempDir::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(src.path().join("**/*.smly"))
+        .arg("-o")
+        .arg(out.path());
+
+    cmd.assert().success();
+
+    // the out files mirror the paths relative to the base of the
+    // pattern, while the partials are skipped
+    assert!(out.child("main.css").exists());
+    assert!(out.child("pages/home.css").exists());
+    assert!(out.child("blog/home.css").exists());
+    assert!(!out.child("_partial.css").exists());
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(src.child("pages/home.smly").path())
+        .arg(src.child("blog/home.smly").path())
+        .arg("-o")
+        .arg(out.path());
+
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("are compiled into"));
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(src.child("pages/home.smly").path())
+        .arg(src.child("blog/home.smly").path())
+        .arg("-o")
+        .arg("-");
+
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("to stdout"));
+
+    assert!(!Path::new("-").exists());
+}
+
+#[test]
+fn run_with_invalid_src_files() {
+    let src = TempDir::new().unwrap();
+    src.child("main.smly")
+        .write_str(".a\n    color: red\n")
+        .unwrap();
+    src.child("binary.smly")
+        .write_binary(&[0xff, 0xfe, 0x00])
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(src.child("missing.smly").path())
+        .arg(src.child("binary.smly").path())
+        .arg(src.child("main.smly").path());
+
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("File does not exist"))
+        .stderr(predicate::str::contains("Unable to read file"));
+
+    assert!(src.child("main.css").exists());
+}
+
+#[test]
+fn run_with_config_file() {
+    let dir = TempDir::new().unwrap();
+    dir.child("smiley.toml")
+        .write_str(indoc! {r#"
+            entries = ["styles/*.smly"]
+            out-dir = "dist"
+            style = "compact"
+
+            [defines]
+            gap = "4px"
+        "#})
+        .unwrap();
+
+    dir.child("styles/main.smly")
+        .write_str(".a\n    margin: $gap\n")
+        .unwrap();
+
+    // the config file is discovered from the parent directories
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.current_dir(dir.child("styles").path());
+
+    cmd.assert().success();
+
+    assert_eq!(
+        fs::read_to_string(dir.child("dist/main.css").path()).unwrap(),
+        ".a { margin: 4px; }\n"
+    );
+
+    // while the command line options take precedence
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.current_dir(dir.path())
+        .arg("styles/main.smly")
+        .arg("--style")
+        .arg("compressed");
+
+    cmd.assert().success();
+
+    assert_eq!(
+        fs::read_to_string(dir.child("dist/main.css").path()).unwrap(),
+        ".a{margin:4px}\n"
+    );
+}
+
+#[test]
+fn run_with_flags_overriding_config_file() {
+    let dir = TempDir::new().unwrap();
+    dir.child("smiley.toml")
+        .write_str(indoc! {"
+            source-map = true
+            line-comments = true
+        "})
+        .unwrap();
+
+    dir.child("main.smly")
+        .write_str(".a\n    color: red\n")
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.current_dir(dir.path())
+        .arg("main.smly")
+        .arg("--no-source-map")
+        .arg("--line-comments")
+        .arg("--no-line-comments");
+
+    cmd.assert().success();
+
+    assert_eq!(
+        fs::read_to_string(dir.child("main.css").path()).unwrap(),
+        ".a {\n  color: red;\n}\n"
+    );
+    assert!(!dir.child("main.css.map").exists());
+
+    // the last one of the overriding flags is used
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.current_dir(dir.path())
+        .arg("main.smly")
+        .arg("--no-source-map")
+        .arg("--source-map");
+
+    cmd.assert().success();
+
+    assert!(dir.child("main.css.map").exists());
+}
+
+#[test]
+fn run_with_invalid_declaration() {
+    let file = NamedTempFile::new("invalid.smly").unwrap();
+    file.write_str(indoc! {"
+        .a
+            color red
+    "})
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(file.path());
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid declaration"));
+}
+
+#[test]
+fn run_with_comments() {
+    let file = NamedTempFile::new("comments.smly").unwrap();
+    file.write_str(indoc! {"
+        /*! license */
+        // dropped
+        .a // dropped
+          /* spanning
+          multiple lines */
+            color: red // dropped
+    "})
+        .unwrap();
+
+    let out = NamedTempFile::new("comments.css").unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(file.path()).arg("-o").arg(out.path());
+
+    cmd.assert().success();
+
+    let expected = indoc! {"
+        /*! license */
+
+        /* spanning
+          multiple lines */
+
+        .a {
+          color: red;
+        }
+    "};
+
+    assert_eq!(fs::read_to_string(out.path()).unwrap(), expected);
+}
+
+#[test]
+fn run_with_directives() {
+    let file = NamedTempFile::new("directives.smly").unwrap();
+    file.write_str(indoc! {"
+        $size: 10px
+        .a
+            @warn \"Deprecated size\"
+            @debug $size * 2
+            width: $size
+    "})
+        .unwrap();
+
+    let out = NamedTempFile::new("directives.css").unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(file.path()).arg("-o").arg(out.path()).arg("-v");
+
+    let location = format!("{}:", file.path().display());
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "{location}3:4: Deprecated size"
+        )))
+        .stderr(predicate::str::contains(format!("{location}4:4: 20px")));
+
+    let expected = indoc! {"
+        .a {
+          width: 10px;
+        }
+    "};
+
+    assert_eq!(fs::read_to_string(out.path()).unwrap(), expected);
+
+    file.write_str(indoc! {"
+        @error \"Unsupported\"
+    "})
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("smiley").unwrap();
+    cmd.arg(file.path()).arg("-o").arg(out.path());
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Unsupported"));
+}